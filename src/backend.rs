@@ -0,0 +1,230 @@
+//! Backends that implement the low-level terminal functionality used by [`Terminal`](crate::Terminal).
+//!
+//! The backends shipped with this crate are selected using cargo features:
+//! `crossterm` (enabled by default) and `termion`.
+//! Other backends can be used by implementing [`Backend`].
+
+#[cfg(feature = "crossterm")]
+mod crossterm;
+#[cfg(feature = "termion")]
+mod termion;
+#[cfg(test)]
+pub(crate) mod test;
+
+#[cfg(feature = "crossterm")]
+pub use self::crossterm::CrosstermBackend;
+#[cfg(feature = "termion")]
+pub use self::termion::TermionBackend;
+
+use crate::{
+    options::CursorShape,
+    style::Attributes,
+    util::{Color, Point, Size},
+    MouseCapture, Result,
+};
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+use std::io::Write;
+use std::{io, time::Duration};
+
+/// The operations a terminal implementation has to provide.
+///
+/// Everything written through [`io::Write`] is expected to be buffered until [`io::Write::flush`] is used.
+pub trait Backend: io::Write {
+    /// Drops what was written since the last flush without writing it.
+    ///
+    /// This is used when the program panics, so that a half-drawn frame or modes the panic hook already undid
+    /// are not written after it. The default implementation does nothing.
+    fn discard_output(&mut self) {}
+
+    fn enter_alternate_dimension(&mut self) -> Result<()>;
+    fn exit_alternate_dimension(&mut self) -> Result<()>;
+
+    fn set_title(&mut self, title: &str) -> Result<()>;
+
+    fn enable_raw_mode(&mut self) -> Result<()>;
+    fn disable_raw_mode(&mut self) -> Result<()>;
+
+    /// Enables reporting of the mouse events `capture` describes in the SGR format, with the rxvt format as a fallback.
+    fn enable_mouse_capture(&mut self, capture: MouseCapture) -> Result<()> {
+        let tracking: &[u8] = match capture {
+            MouseCapture::Clicks => b"\x1b[?1000h",
+            MouseCapture::Drag => b"\x1b[?1000h\x1b[?1002h",
+            MouseCapture::All => b"\x1b[?1000h\x1b[?1002h\x1b[?1003h",
+        };
+        self.write_all(tracking)?;
+        self.write_all(b"\x1b[?1015h\x1b[?1006h")?;
+        Ok(())
+    }
+    fn disable_mouse_capture(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[?1006l\x1b[?1015l\x1b[?1003l\x1b[?1002l\x1b[?1000l")?;
+        Ok(())
+    }
+
+    fn enable_bracketed_paste(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[?2004h")?;
+        Ok(())
+    }
+    fn disable_bracketed_paste(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[?2004l")?;
+        Ok(())
+    }
+
+    fn enable_focus_reporting(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[?1004h")?;
+        Ok(())
+    }
+    fn disable_focus_reporting(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[?1004l")?;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> Result<()>;
+    fn hide_cursor(&mut self) -> Result<()>;
+
+    fn set_cursor_shape(&mut self, shape: CursorShape) -> Result<()> {
+        write!(self, "\x1b[{} q", shape.parameter())?;
+        Ok(())
+    }
+
+    /// Reads raw input from the terminal into `buffer` and returns the amount of bytes read.
+    ///
+    /// This waits at most `timeout` or forever if it is `None`.
+    /// `0` is returned if no input arrived in time or if the wait was cut short, for example because the terminal was resized.
+    ///
+    /// The input is decoded into events by [`Terminal`](crate::Terminal).
+    fn read_input(&mut self, buffer: &mut [u8], timeout: Option<Duration>) -> Result<usize>;
+
+    /// Sets the cursor to the top left corner.
+    fn reset_cursor(&mut self) -> Result<()> {
+        self.set_cursor(Point::default())
+    }
+
+    fn set_cursor(&mut self, point: Point) -> Result<()>;
+    fn set_cursor_x(&mut self, x: u16) -> Result<()>;
+    fn set_cursor_y(&mut self, y: u16) -> Result<()>;
+
+    fn move_cursor_up_by(&mut self, cells: u16) -> Result<()>;
+    fn move_cursor_down_by(&mut self, cells: u16) -> Result<()>;
+    fn move_cursor_left_by(&mut self, cells: u16) -> Result<()>;
+    fn move_cursor_right_by(&mut self, cells: u16) -> Result<()>;
+
+    fn move_cursor_up(&mut self) -> Result<()> {
+        self.move_cursor_up_by(1)
+    }
+    fn move_cursor_down(&mut self) -> Result<()> {
+        self.move_cursor_down_by(1)
+    }
+    fn move_cursor_left(&mut self) -> Result<()> {
+        self.move_cursor_left_by(1)
+    }
+    fn move_cursor_right(&mut self) -> Result<()> {
+        self.move_cursor_right_by(1)
+    }
+
+    fn next_line(&mut self) -> Result<()>;
+    fn previous_line(&mut self) -> Result<()>;
+
+    fn save_cursor_point(&mut self) -> Result<()>;
+    fn restore_cursor_point(&mut self) -> Result<()>;
+
+    fn set_foreground_color(&mut self, color: Color) -> Result<()>;
+    fn set_background_color(&mut self, color: Color) -> Result<()>;
+    /// Makes the text that follows have the terminal's default color.
+    fn reset_foreground_color(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[39m")?;
+        Ok(())
+    }
+    /// Makes the text that follows have the terminal's default background color.
+    fn reset_background_color(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[49m")?;
+        Ok(())
+    }
+    /// Resets the colors and attributes to the terminal's defaults.
+    fn reset_colors(&mut self) -> Result<()>;
+
+    /// Sets the color of underlines.
+    fn set_underline_color(&mut self, color: Color) -> Result<()> {
+        let parameters = match color {
+            Color::Rgb { r, g, b } => format!("2;{};{};{}", r, g, b),
+            Color::Byte(byte) => format!("5;{}", byte),
+            color => format!("5;{}", color.four_bit_index().unwrap_or_default()),
+        };
+        write!(self, "\x1b[58;{}m", parameters)?;
+        Ok(())
+    }
+    /// Makes underlines have the color of the text again.
+    fn reset_underline_color(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[59m")?;
+        Ok(())
+    }
+
+    /// Enables `attributes` while leaving the others as they are.
+    fn enable_attributes(&mut self, attributes: Attributes) -> Result<()> {
+        let parameters: Vec<_> = attributes.enable_parameters().collect();
+        write!(self, "\x1b[{}m", parameters.join(";"))?;
+        Ok(())
+    }
+    /// Disables `attributes` while leaving the others as they are,
+    /// except that disabling bold or dim disables both and disabling any underline disables all underlines.
+    fn disable_attributes(&mut self, attributes: Attributes) -> Result<()> {
+        let parameters: Vec<_> = attributes.disable_parameters().collect();
+        write!(self, "\x1b[{}m", parameters.join(";"))?;
+        Ok(())
+    }
+
+    fn enable_italic(&mut self) -> Result<()> {
+        self.enable_attributes(Attributes::ITALIC)
+    }
+    fn disable_italic(&mut self) -> Result<()> {
+        self.disable_attributes(Attributes::ITALIC)
+    }
+
+    fn clear(&mut self) -> Result<()>;
+    fn clear_from_cursor_to_end(&mut self) -> Result<()>;
+
+    fn size(&self) -> Result<Size>;
+}
+
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+/// Buffers output until it is flushed like [`io::BufWriter`], but can also drop it.
+#[derive(Debug)]
+pub(crate) struct OutputBuffer<W: Write> {
+    buffer: Vec<u8>,
+    writer: W,
+}
+
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+impl<W: Write> OutputBuffer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            buffer: Vec::new(),
+            writer,
+        }
+    }
+
+    pub fn discard(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+impl<W: Write> Write for OutputBuffer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.writer.write_all(&self.buffer);
+        self.buffer.clear();
+        result?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+impl<W: Write> Drop for OutputBuffer<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
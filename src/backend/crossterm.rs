@@ -0,0 +1,426 @@
+//! Backend implementation using the `crossterm` crate.
+
+use super::{Backend, OutputBuffer};
+#[cfg(not(unix))]
+use crate::event::Modifiers;
+#[cfg(unix)]
+use crate::sys;
+#[cfg(target_os = "windows")]
+use crate::MouseCapture;
+use crate::{
+    util::{Color, Point, Size},
+    Error, Result,
+};
+#[cfg(not(unix))]
+use crossterm::event;
+use crossterm::{cursor, style, terminal, QueueableCommand};
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+// > When I first did this, it was noticeably slower than the termion version(roughly 5-10 fps).
+// > This is because calling into the Console API that often (once per character) is going to pull down performance.
+// > Luckily, I could work around this by just checking if we were already using the color I wanted to render.
+// > If we were, I didn't set the color again.
+//
+// `Terminal` does this for all backends by keeping track of the current colors.
+
+/// A backend that uses `crossterm` and works on all platforms supported by it.
+#[derive(Debug)]
+pub struct CrosstermBackend<W: Write> {
+    stdout: OutputBuffer<W>,
+    /// Opened once input is read for the first time.
+    #[cfg(unix)]
+    tty: Option<sys::Tty>,
+    /// Input that was encoded from events but did not fit into the buffer passed to `read_input` yet.
+    #[cfg(not(unix))]
+    input: Vec<u8>,
+}
+
+impl<W: Write> CrosstermBackend<W> {
+    pub fn new(stdout: W) -> Self {
+        Self {
+            stdout: OutputBuffer::new(stdout),
+            #[cfg(unix)]
+            tty: None,
+            #[cfg(not(unix))]
+            input: Vec::new(),
+        }
+    }
+
+    fn convert_color(color: Color) -> style::Color {
+        match color {
+            Color::Black => style::Color::Black,
+            Color::DarkGray => style::Color::DarkGrey,
+            Color::Red => style::Color::Red,
+            Color::DarkRed => style::Color::DarkRed,
+            Color::Green => style::Color::Green,
+            Color::DarkGreen => style::Color::DarkGreen,
+            Color::Yellow => style::Color::Yellow,
+            Color::DarkYellow => style::Color::DarkYellow,
+            Color::Blue => style::Color::Blue,
+            Color::DarkBlue => style::Color::DarkBlue,
+            Color::Magenta => style::Color::Magenta,
+            Color::DarkMagenta => style::Color::DarkMagenta,
+            Color::Cyan => style::Color::Cyan,
+            Color::DarkCyan => style::Color::DarkCyan,
+            Color::White => style::Color::White,
+            Color::Gray => style::Color::Grey,
+            Color::Rgb { r, g, b } => style::Color::Rgb { r, g, b },
+            Color::Byte(rgb) => style::Color::AnsiValue(rgb),
+        }
+    }
+}
+
+impl<W: Write> Write for CrosstermBackend<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdout.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdout.flush()
+    }
+}
+
+impl<W: Write> Backend for CrosstermBackend<W> {
+    fn discard_output(&mut self) {
+        self.stdout.discard();
+    }
+
+    fn enter_alternate_dimension(&mut self) -> Result<()> {
+        self.stdout.queue(terminal::EnterAlternateScreen)?;
+        Ok(())
+    }
+    fn exit_alternate_dimension(&mut self) -> Result<()> {
+        self.stdout.queue(terminal::LeaveAlternateScreen)?;
+        Ok(())
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<()> {
+        self.stdout.queue(terminal::SetTitle(title))?;
+        Ok(())
+    }
+
+    fn enable_raw_mode(&mut self) -> Result<()> {
+        terminal::enable_raw_mode()?;
+        Ok(())
+    }
+    fn disable_raw_mode(&mut self) -> Result<()> {
+        terminal::disable_raw_mode()?;
+        Ok(())
+    }
+
+    // The Console API does not distinguish between the kinds of mouse events
+    #[cfg(target_os = "windows")]
+    fn enable_mouse_capture(&mut self, _capture: MouseCapture) -> Result<()> {
+        self.stdout.queue(event::EnableMouseCapture)?;
+        Ok(())
+    }
+    #[cfg(target_os = "windows")]
+    fn disable_mouse_capture(&mut self) -> Result<()> {
+        self.stdout.queue(event::DisableMouseCapture)?;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> Result<()> {
+        self.stdout.queue(cursor::Show)?;
+        Ok(())
+    }
+    fn hide_cursor(&mut self) -> Result<()> {
+        self.stdout.queue(cursor::Hide)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn read_input(&mut self, buffer: &mut [u8], timeout: Option<Duration>) -> Result<usize> {
+        let tty = match &mut self.tty {
+            Some(tty) => tty,
+            None => self.tty.insert(sys::Tty::new()?),
+        };
+        Ok(tty.read(buffer, timeout)?)
+    }
+
+    // The console does not send escape sequences for most keys and the mouse,
+    // so the events crossterm reads are encoded into the ones terminals send elsewhere
+    #[cfg(not(unix))]
+    fn read_input(&mut self, buffer: &mut [u8], timeout: Option<Duration>) -> Result<usize> {
+        if self.input.is_empty() {
+            let ready = match timeout {
+                Some(timeout) => event::poll(timeout)?,
+                None => true,
+            };
+            // Everything available is read at once so that sequences that arrive as separate key presses,
+            // such as replies to queries, are decoded together
+            while ready && self.input.len() < buffer.len() {
+                match event::read()? {
+                    event::Event::Key(key_event) => encode_key(key_event, &mut self.input),
+                    event::Event::Mouse(mouse_event) => encode_mouse(mouse_event, &mut self.input),
+                    // The size is checked after every read
+                    event::Event::Resize(_, _) => {}
+                }
+                if !event::poll(Duration::ZERO)? {
+                    break;
+                }
+            }
+        }
+
+        let count = self.input.len().min(buffer.len());
+        buffer[..count].copy_from_slice(&self.input[..count]);
+        self.input.drain(..count);
+        Ok(count)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn reset_cursor(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[;H")?;
+        Ok(())
+    }
+
+    /// If possible, try to use the `move_cursor_{}_by` and `move_cursor_{}` methods instead for single operations.
+    fn set_cursor(&mut self, point: Point) -> Result<()> {
+        self.stdout.queue(cursor::MoveTo(point.x, point.y))?;
+        Ok(())
+    }
+
+    // crossterm's `MoveToColumn` and `MoveToRow` send the 0-based values as they are, which is one too early
+    fn set_cursor_x(&mut self, x: u16) -> Result<()> {
+        write!(self.stdout, "\u{1b}[{}G", x + 1)?;
+        Ok(())
+    }
+
+    fn set_cursor_y(&mut self, y: u16) -> Result<()> {
+        write!(self.stdout, "\u{1b}[{}d", y + 1)?;
+        Ok(())
+    }
+
+    fn move_cursor_up_by(&mut self, cells: u16) -> Result<()> {
+        self.stdout.queue(cursor::MoveUp(cells))?;
+        Ok(())
+    }
+    fn move_cursor_down_by(&mut self, cells: u16) -> Result<()> {
+        self.stdout.queue(cursor::MoveDown(cells))?;
+        Ok(())
+    }
+    fn move_cursor_left_by(&mut self, cells: u16) -> Result<()> {
+        self.stdout.queue(cursor::MoveLeft(cells))?;
+        Ok(())
+    }
+    fn move_cursor_right_by(&mut self, cells: u16) -> Result<()> {
+        self.stdout.queue(cursor::MoveRight(cells))?;
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn move_cursor_up(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[A")?;
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    fn move_cursor_down(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[B")?;
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    fn move_cursor_left(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[D")?;
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    fn move_cursor_right(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[C")?;
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn next_line(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[E")?;
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    fn previous_line(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[F")?;
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    fn next_line(&mut self) -> Result<()> {
+        self.stdout.queue(cursor::MoveToNextLine(1))?;
+        Ok(())
+    }
+    #[cfg(target_os = "windows")]
+    fn previous_line(&mut self) -> Result<()> {
+        self.stdout.queue(cursor::MoveToPreviousLine(1))?;
+        Ok(())
+    }
+
+    fn save_cursor_point(&mut self) -> Result<()> {
+        self.stdout.queue(cursor::SavePosition)?;
+        Ok(())
+    }
+    fn restore_cursor_point(&mut self) -> Result<()> {
+        self.stdout.queue(cursor::RestorePosition)?;
+        Ok(())
+    }
+
+    fn set_foreground_color(&mut self, color: Color) -> Result<()> {
+        self.stdout
+            .queue(style::SetForegroundColor(Self::convert_color(color)))?;
+        Ok(())
+    }
+    fn set_background_color(&mut self, color: Color) -> Result<()> {
+        self.stdout
+            .queue(style::SetBackgroundColor(Self::convert_color(color)))?;
+        Ok(())
+    }
+    fn reset_foreground_color(&mut self) -> Result<()> {
+        self.stdout
+            .queue(style::SetForegroundColor(style::Color::Reset))?;
+        Ok(())
+    }
+    fn reset_background_color(&mut self) -> Result<()> {
+        self.stdout
+            .queue(style::SetBackgroundColor(style::Color::Reset))?;
+        Ok(())
+    }
+    fn reset_colors(&mut self) -> Result<()> {
+        self.stdout.queue(style::ResetColor)?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.stdout
+            .queue(terminal::Clear(terminal::ClearType::All))?;
+        Ok(())
+    }
+    fn clear_from_cursor_to_end(&mut self) -> Result<()> {
+        self.stdout
+            .queue(terminal::Clear(terminal::ClearType::FromCursorDown))?;
+        Ok(())
+    }
+
+    fn size(&self) -> Result<Size> {
+        let (width, height) = terminal::size()?;
+        // This happens when the terminal does not know its size, such as some serial consoles
+        if width == 0 || height == 0 {
+            return Err(Error::Backend(
+                format!("terminal reported a size of {}x{}", width, height).into(),
+            ));
+        }
+        Ok(Size { width, height })
+    }
+}
+
+#[cfg(not(unix))]
+fn convert_modifiers(modifiers: event::KeyModifiers) -> Modifiers {
+    let mut converted = Modifiers::empty();
+    converted.set(
+        Modifiers::SHIFT,
+        modifiers.contains(event::KeyModifiers::SHIFT),
+    );
+    converted.set(Modifiers::ALT, modifiers.contains(event::KeyModifiers::ALT));
+    converted.set(
+        Modifiers::CTRL,
+        modifiers.contains(event::KeyModifiers::CONTROL),
+    );
+    converted
+}
+
+/// Appends the escape sequence for `key_event` to `input`, using the kitty keyboard protocol where others are ambiguous.
+#[cfg(not(unix))]
+fn encode_key(key_event: event::KeyEvent, input: &mut Vec<u8>) {
+    use event::KeyCode;
+
+    let mut modifiers = key_event.modifiers;
+    if let KeyCode::Char(_) = key_event.code {
+        // The character already tells whether Shift was held down
+        modifiers -= event::KeyModifiers::SHIFT;
+    }
+    // The modifier parameter is one more than the bits of `Modifiers`
+    let parameter = convert_modifiers(modifiers).bits() + 1;
+    let sequence = match key_event.code {
+        KeyCode::Char(char) if modifiers.is_empty() => char.to_string(),
+        KeyCode::Enter if modifiers.is_empty() => "\r".to_string(),
+        KeyCode::Tab if modifiers.is_empty() => "\t".to_string(),
+        KeyCode::Backspace if modifiers.is_empty() => "\x7f".to_string(),
+        KeyCode::Esc if modifiers.is_empty() => "\x1b".to_string(),
+        KeyCode::Char(char) => format!("\x1b[{};{}u", char as u32, parameter),
+        KeyCode::Enter => format!("\x1b[13;{}u", parameter),
+        KeyCode::Tab => format!("\x1b[9;{}u", parameter),
+        KeyCode::Backspace => format!("\x1b[127;{}u", parameter),
+        KeyCode::Esc => format!("\x1b[27;{}u", parameter),
+        KeyCode::BackTab => "\x1b[Z".to_string(),
+        KeyCode::Up => format!("\x1b[1;{}A", parameter),
+        KeyCode::Down => format!("\x1b[1;{}B", parameter),
+        KeyCode::Right => format!("\x1b[1;{}C", parameter),
+        KeyCode::Left => format!("\x1b[1;{}D", parameter),
+        KeyCode::Home => format!("\x1b[1;{}H", parameter),
+        KeyCode::End => format!("\x1b[1;{}F", parameter),
+        KeyCode::Insert => format!("\x1b[2;{}~", parameter),
+        KeyCode::Delete => format!("\x1b[3;{}~", parameter),
+        KeyCode::PageUp => format!("\x1b[5;{}~", parameter),
+        KeyCode::PageDown => format!("\x1b[6;{}~", parameter),
+        KeyCode::F(number) => {
+            // The numbers of the function keys have gaps
+            let offset = match number {
+                1..=5 => 10,
+                6..=10 => 11,
+                11..=14 => 12,
+                15..=16 => 13,
+                17..=20 => 14,
+                _ => return,
+            };
+            format!("\x1b[{};{}~", number + offset, parameter)
+        }
+        KeyCode::Null => "\0".to_string(),
+    };
+    input.extend_from_slice(sequence.as_bytes());
+}
+
+/// Appends the escape sequence for `mouse_event` in the SGR format to `input`.
+#[cfg(not(unix))]
+fn encode_mouse(mouse_event: event::MouseEvent, input: &mut Vec<u8>) {
+    use event::{MouseButton, MouseEventKind};
+
+    let button = |button| match button {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+    };
+    let (mut code, released) = match mouse_event.kind {
+        MouseEventKind::Down(pressed) => (button(pressed), false),
+        MouseEventKind::Up(released) => (button(released), true),
+        MouseEventKind::Drag(held) => (button(held) | 0b10_0000, false),
+        MouseEventKind::Moved => (0b10_0011, false),
+        MouseEventKind::ScrollUp => (0b100_0000, false),
+        MouseEventKind::ScrollDown => (0b100_0001, false),
+    };
+    // The modifiers are in the bits above the button
+    code |= convert_modifiers(mouse_event.modifiers).bits() << 2;
+
+    let sequence = format!(
+        "\x1b[<{};{};{}{}",
+        code,
+        mouse_event.column as u32 + 1,
+        mouse_event.row as u32 + 1,
+        if released { 'm' } else { 'M' }
+    );
+    input.extend_from_slice(sequence.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_cursor() {
+        let mut output = Vec::new();
+        let mut backend = CrosstermBackend::new(&mut output);
+        backend.set_cursor(Point { x: 0, y: 0 }).unwrap();
+        backend.set_cursor_x(0).unwrap();
+        backend.set_cursor_y(4).unwrap();
+        backend.flush().unwrap();
+        drop(backend);
+        assert_eq!(output, b"\x1b[1;1H\x1b[1G\x1b[5d");
+    }
+}
@@ -0,0 +1,244 @@
+//! Backend implementation using the `termion` crate, mainly for the Redox operating system.
+
+use super::{Backend, OutputBuffer};
+use crate::{
+    sys,
+    util::{Color, Point, Size},
+    Error, Result,
+};
+use std::{
+    fmt,
+    io::{self, Write},
+    time::Duration,
+};
+use termion::{
+    clear, color, cursor,
+    raw::{IntoRawMode, RawTerminal},
+    screen, style,
+};
+
+/// A backend that uses `termion` and works on Unix-like operating systems including Redox.
+pub struct TermionBackend<W: Write> {
+    stdout: OutputBuffer<W>,
+    /// Restores the previous terminal mode when dropped.
+    raw_mode: Option<RawTerminal<io::Sink>>,
+    /// Opened once input is read for the first time.
+    tty: Option<sys::Tty>,
+}
+
+impl<W: Write> TermionBackend<W> {
+    pub fn new(stdout: W) -> Self {
+        Self {
+            stdout: OutputBuffer::new(stdout),
+            raw_mode: None,
+            tty: None,
+        }
+    }
+
+    fn convert_color(color: Color) -> Box<dyn color::Color> {
+        match color {
+            Color::Black => Box::new(color::Black),
+            Color::DarkGray => Box::new(color::LightBlack),
+            Color::Red => Box::new(color::LightRed),
+            Color::DarkRed => Box::new(color::Red),
+            Color::Green => Box::new(color::LightGreen),
+            Color::DarkGreen => Box::new(color::Green),
+            Color::Yellow => Box::new(color::LightYellow),
+            Color::DarkYellow => Box::new(color::Yellow),
+            Color::Blue => Box::new(color::LightBlue),
+            Color::DarkBlue => Box::new(color::Blue),
+            Color::Magenta => Box::new(color::LightMagenta),
+            Color::DarkMagenta => Box::new(color::Magenta),
+            Color::Cyan => Box::new(color::LightCyan),
+            Color::DarkCyan => Box::new(color::Cyan),
+            Color::White => Box::new(color::LightWhite),
+            Color::Gray => Box::new(color::White),
+            Color::Rgb { r, g, b } => Box::new(color::Rgb(r, g, b)),
+            Color::Byte(byte) => Box::new(color::AnsiValue(byte)),
+        }
+    }
+}
+
+impl<W: Write> fmt::Debug for TermionBackend<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TermionBackend")
+            .field("raw_mode", &self.raw_mode.is_some())
+            .field("tty", &self.tty)
+            .finish()
+    }
+}
+
+impl<W: Write> Write for TermionBackend<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdout.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdout.flush()
+    }
+}
+
+impl<W: Write> Backend for TermionBackend<W> {
+    fn discard_output(&mut self) {
+        self.stdout.discard();
+    }
+
+    fn enter_alternate_dimension(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", screen::ToAlternateScreen)?;
+        Ok(())
+    }
+    fn exit_alternate_dimension(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", screen::ToMainScreen)?;
+        Ok(())
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<()> {
+        write!(self.stdout, "\u{1b}]0;{}\u{7}", title)?;
+        Ok(())
+    }
+
+    fn enable_raw_mode(&mut self) -> Result<()> {
+        if self.raw_mode.is_none() {
+            self.raw_mode = Some(io::sink().into_raw_mode()?);
+        }
+        Ok(())
+    }
+    fn disable_raw_mode(&mut self) -> Result<()> {
+        if let Some(raw_mode) = self.raw_mode.take() {
+            raw_mode.suspend_raw_mode()?;
+        }
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", cursor::Show)?;
+        Ok(())
+    }
+    fn hide_cursor(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", cursor::Hide)?;
+        Ok(())
+    }
+
+    fn read_input(&mut self, buffer: &mut [u8], timeout: Option<Duration>) -> Result<usize> {
+        let tty = match &mut self.tty {
+            Some(tty) => tty,
+            None => self.tty.insert(sys::Tty::new()?),
+        };
+        Ok(tty.read(buffer, timeout)?)
+    }
+
+    fn reset_cursor(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[;H")?;
+        Ok(())
+    }
+
+    fn set_cursor(&mut self, point: Point) -> Result<()> {
+        write!(self.stdout, "{}", cursor::Goto(point.x + 1, point.y + 1))?;
+        Ok(())
+    }
+
+    fn set_cursor_x(&mut self, x: u16) -> Result<()> {
+        write!(self.stdout, "\u{1b}[{}G", x + 1)?;
+        Ok(())
+    }
+
+    fn set_cursor_y(&mut self, y: u16) -> Result<()> {
+        write!(self.stdout, "\u{1b}[{}d", y + 1)?;
+        Ok(())
+    }
+
+    fn move_cursor_up_by(&mut self, cells: u16) -> Result<()> {
+        write!(self.stdout, "{}", cursor::Up(cells))?;
+        Ok(())
+    }
+    fn move_cursor_down_by(&mut self, cells: u16) -> Result<()> {
+        write!(self.stdout, "{}", cursor::Down(cells))?;
+        Ok(())
+    }
+    fn move_cursor_left_by(&mut self, cells: u16) -> Result<()> {
+        write!(self.stdout, "{}", cursor::Left(cells))?;
+        Ok(())
+    }
+    fn move_cursor_right_by(&mut self, cells: u16) -> Result<()> {
+        write!(self.stdout, "{}", cursor::Right(cells))?;
+        Ok(())
+    }
+
+    fn move_cursor_up(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[A")?;
+        Ok(())
+    }
+    fn move_cursor_down(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[B")?;
+        Ok(())
+    }
+    fn move_cursor_left(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[D")?;
+        Ok(())
+    }
+    fn move_cursor_right(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[C")?;
+        Ok(())
+    }
+
+    fn next_line(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[E")?;
+        Ok(())
+    }
+    fn previous_line(&mut self) -> Result<()> {
+        self.stdout.write_all(b"\x1b[F")?;
+        Ok(())
+    }
+
+    fn save_cursor_point(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", cursor::Save)?;
+        Ok(())
+    }
+    fn restore_cursor_point(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", cursor::Restore)?;
+        Ok(())
+    }
+
+    fn set_foreground_color(&mut self, color: Color) -> Result<()> {
+        let color = Self::convert_color(color);
+        write!(self.stdout, "{}", color::Fg(color.as_ref()))?;
+        Ok(())
+    }
+    fn set_background_color(&mut self, color: Color) -> Result<()> {
+        let color = Self::convert_color(color);
+        write!(self.stdout, "{}", color::Bg(color.as_ref()))?;
+        Ok(())
+    }
+    fn reset_foreground_color(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", color::Fg(color::Reset))?;
+        Ok(())
+    }
+    fn reset_background_color(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", color::Bg(color::Reset))?;
+        Ok(())
+    }
+    fn reset_colors(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", style::Reset)?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", clear::All)?;
+        Ok(())
+    }
+    fn clear_from_cursor_to_end(&mut self) -> Result<()> {
+        write!(self.stdout, "{}", clear::AfterCursor)?;
+        Ok(())
+    }
+
+    fn size(&self) -> Result<Size> {
+        let (width, height) = termion::terminal_size()?;
+        // This happens when the terminal does not know its size, such as some serial consoles
+        if width == 0 || height == 0 {
+            return Err(Error::Backend(
+                format!("terminal reported a size of {}x{}", width, height).into(),
+            ));
+        }
+        Ok(Size { width, height })
+    }
+}
@@ -0,0 +1,171 @@
+//! A backend that records its output and replays scripted input, for testing [`Terminal`](crate::Terminal).
+
+use super::Backend;
+use crate::{
+    util::{Color, Point, Size},
+    Result,
+};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io::{self, Write},
+    rc::Rc,
+    time::Duration,
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct TestBackend {
+    /// Everything that was flushed. It is shared so that it can be inspected after the terminal was dropped.
+    pub output: Rc<RefCell<Vec<u8>>>,
+    buffer: Vec<u8>,
+    /// Each entry is returned by one call to `read_input`.
+    pub input: VecDeque<Vec<u8>>,
+    pub size: Size,
+}
+
+impl TestBackend {
+    pub fn new(size: Size) -> Self {
+        Self {
+            size,
+            ..Self::default()
+        }
+    }
+
+    /// Returns and clears what was flushed so far.
+    pub fn take_output(&self) -> String {
+        String::from_utf8(self.output.take()).unwrap()
+    }
+}
+
+impl Write for TestBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.borrow_mut().append(&mut self.buffer);
+        Ok(())
+    }
+}
+
+impl Backend for TestBackend {
+    fn discard_output(&mut self) {
+        self.buffer.clear();
+    }
+
+    fn enter_alternate_dimension(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[?1049h")?;
+        Ok(())
+    }
+    fn exit_alternate_dimension(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[?1049l")?;
+        Ok(())
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<()> {
+        write!(self, "\x1b]0;{}\x07", title)?;
+        Ok(())
+    }
+
+    fn enable_raw_mode(&mut self) -> Result<()> {
+        self.write_all(b"<raw>")?;
+        Ok(())
+    }
+    fn disable_raw_mode(&mut self) -> Result<()> {
+        self.write_all(b"</raw>")?;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[?25h")?;
+        Ok(())
+    }
+    fn hide_cursor(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[?25l")?;
+        Ok(())
+    }
+
+    fn read_input(&mut self, buffer: &mut [u8], _timeout: Option<Duration>) -> Result<usize> {
+        match self.input.pop_front() {
+            Some(input) => {
+                buffer[..input.len()].copy_from_slice(&input);
+                Ok(input.len())
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn set_cursor(&mut self, point: Point) -> Result<()> {
+        write!(self, "\x1b[{};{}H", point.y + 1, point.x + 1)?;
+        Ok(())
+    }
+    fn set_cursor_x(&mut self, x: u16) -> Result<()> {
+        write!(self, "\x1b[{}G", x + 1)?;
+        Ok(())
+    }
+    fn set_cursor_y(&mut self, y: u16) -> Result<()> {
+        write!(self, "\x1b[{}d", y + 1)?;
+        Ok(())
+    }
+
+    fn move_cursor_up_by(&mut self, cells: u16) -> Result<()> {
+        write!(self, "\x1b[{}A", cells)?;
+        Ok(())
+    }
+    fn move_cursor_down_by(&mut self, cells: u16) -> Result<()> {
+        write!(self, "\x1b[{}B", cells)?;
+        Ok(())
+    }
+    fn move_cursor_left_by(&mut self, cells: u16) -> Result<()> {
+        write!(self, "\x1b[{}D", cells)?;
+        Ok(())
+    }
+    fn move_cursor_right_by(&mut self, cells: u16) -> Result<()> {
+        write!(self, "\x1b[{}C", cells)?;
+        Ok(())
+    }
+
+    fn next_line(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[E")?;
+        Ok(())
+    }
+    fn previous_line(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[F")?;
+        Ok(())
+    }
+
+    fn save_cursor_point(&mut self) -> Result<()> {
+        self.write_all(b"\x1b7")?;
+        Ok(())
+    }
+    fn restore_cursor_point(&mut self) -> Result<()> {
+        self.write_all(b"\x1b8")?;
+        Ok(())
+    }
+
+    fn set_foreground_color(&mut self, color: Color) -> Result<()> {
+        write!(self, "<fg {:?}>", color)?;
+        Ok(())
+    }
+    fn set_background_color(&mut self, color: Color) -> Result<()> {
+        write!(self, "<bg {:?}>", color)?;
+        Ok(())
+    }
+    fn reset_colors(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[0m")?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[2J")?;
+        Ok(())
+    }
+    fn clear_from_cursor_to_end(&mut self) -> Result<()> {
+        self.write_all(b"\x1b[J")?;
+        Ok(())
+    }
+
+    fn size(&self) -> Result<Size> {
+        Ok(self.size)
+    }
+}
@@ -0,0 +1,306 @@
+//! A grid of cells that can be drawn into and then rendered all at once.
+//!
+//! See [`Terminal::buffer_mut`](crate::Terminal::buffer_mut) and [`Terminal::present`](crate::Terminal::present).
+
+use crate::{
+    style::Style,
+    util::{Point, Size},
+};
+
+/// A single character on the screen and its appearance.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Cell {
+    /// This should be a character that takes up exactly one cell.
+    pub symbol: char,
+    pub style: Style,
+    /// The hyperlink this cell is part of, which belongs to the buffer the cell is in.
+    pub link: Option<LinkRef>,
+}
+
+/// An empty cell in the default style.
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            symbol: ' ',
+            style: Style::default(),
+            link: None,
+        }
+    }
+}
+
+/// A link that text can have, which opens `url` when it is clicked in terminals that support it.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Hyperlink {
+    pub url: String,
+    /// Cells with the same `id` and `url` belong to the same link even if they are not next to each other,
+    /// such as when a link wraps across lines. Otherwise only adjacent cells do.
+    pub id: Option<String>,
+}
+
+impl Hyperlink {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            id: None,
+        }
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+}
+
+/// Refers to a [`Hyperlink`] added to a buffer with [`Buffer::add_hyperlink`].
+///
+/// It can be stored in cells, which keeps them cheap to copy.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct LinkRef(u32);
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Buffer {
+    size: Size,
+    /// The cells from left to right, top to bottom.
+    cells: Vec<Cell>,
+    /// The hyperlinks that [`LinkRef`]s refer to by index.
+    links: Vec<Hyperlink>,
+}
+
+impl Buffer {
+    /// Creates a buffer of `size` filled with empty cells.
+    pub fn new(size: Size) -> Self {
+        Self {
+            size,
+            cells: vec![Cell::default(); size.product() as usize],
+            links: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        if point.x < self.size.width && point.y < self.size.height {
+            Some(point.y as usize * self.size.width as usize + point.x as usize)
+        } else {
+            None
+        }
+    }
+
+    fn point(&self, index: usize) -> Point {
+        let width = self.size.width as usize;
+        Point {
+            x: (index % width) as u16,
+            y: (index / width) as u16,
+        }
+    }
+
+    /// Returns the cell at `point` or `None` if it is out of bounds.
+    pub fn get(&self, point: Point) -> Option<&Cell> {
+        self.index(point).map(|index| &self.cells[index])
+    }
+
+    /// Returns the cell at `point` or `None` if it is out of bounds.
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut Cell> {
+        self.index(point).map(move |index| &mut self.cells[index])
+    }
+
+    /// Sets the cell at `point` to `cell`. Nothing happens if `point` is out of bounds.
+    pub fn set(&mut self, point: Point, cell: Cell) {
+        if let Some(current_cell) = self.get_mut(point) {
+            *current_cell = cell;
+        }
+    }
+
+    /// Writes `string` starting at `point` in `style`.
+    ///
+    /// Characters that go past the end of the row are cut off.
+    pub fn write(&mut self, point: Point, string: &str, style: Style) {
+        self.write_cells(point, string, style, None);
+    }
+
+    /// Writes `string` starting at `point` in `style` as part of `link`. See [`Buffer::write`].
+    pub fn write_link(&mut self, point: Point, string: &str, style: Style, link: LinkRef) {
+        self.write_cells(point, string, style, Some(link));
+    }
+
+    fn write_cells(&mut self, point: Point, string: &str, style: Style, link: Option<LinkRef>) {
+        for (x, symbol) in (point.x..self.size.width).zip(string.chars()) {
+            self.set(
+                Point { x, y: point.y },
+                Cell {
+                    symbol,
+                    style,
+                    link,
+                },
+            );
+        }
+    }
+
+    /// Adds `hyperlink` to this buffer so that cells can refer to it.
+    ///
+    /// Adding the same hyperlink again returns the same reference.
+    /// Hyperlinks stay in the buffer until it is dropped, even if no cell uses them anymore.
+    pub fn add_hyperlink(&mut self, hyperlink: Hyperlink) -> LinkRef {
+        let index = match self.links.iter().position(|link| *link == hyperlink) {
+            Some(index) => index,
+            None => {
+                self.links.push(hyperlink);
+                self.links.len() - 1
+            }
+        };
+        LinkRef(index as u32)
+    }
+
+    /// Returns the hyperlink `link` refers to or `None` if it was not added to this buffer.
+    pub fn hyperlink(&self, link: LinkRef) -> Option<&Hyperlink> {
+        self.links.get(link.0 as usize)
+    }
+
+    /// Returns the hyperlink of `cell`, which must be in this buffer.
+    pub(crate) fn cell_hyperlink(&self, cell: &Cell) -> Option<&Hyperlink> {
+        cell.link.and_then(|link| self.hyperlink(link))
+    }
+
+    /// Sets all cells to empty cells.
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::default());
+    }
+
+    /// Changes the size of the buffer to `size` while keeping the cells that are still in bounds.
+    pub fn resize(&mut self, size: Size) {
+        if size == self.size {
+            return;
+        }
+
+        let mut buffer = Self::new(size);
+        for (index, cell) in self.cells.iter().enumerate() {
+            buffer.set(self.point(index), *cell);
+        }
+        buffer.links = std::mem::take(&mut self.links);
+        *self = buffer;
+    }
+
+    /// Returns the cells of this buffer that are different in `previous`, in order from left to right, top to bottom.
+    ///
+    /// Both buffers must have the same size.
+    /// Hyperlinks are compared by what the cells' [`LinkRef`]s refer to in their buffer.
+    pub fn diff<'a>(
+        &'a self,
+        previous: &'a Buffer,
+    ) -> impl Iterator<Item = (Point, &'a Cell)> + 'a {
+        debug_assert_eq!(self.size, previous.size);
+
+        self.cells
+            .iter()
+            .zip(&previous.cells)
+            .enumerate()
+            .filter(move |(_, (cell, previous_cell))| {
+                cell.symbol != previous_cell.symbol
+                    || cell.style != previous_cell.style
+                    || self.cell_hyperlink(cell) != previous.cell_hyperlink(previous_cell)
+            })
+            .map(move |(index, (cell, _))| (self.point(index), cell))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{style::Attributes, util::Color};
+
+    fn size(width: u16, height: u16) -> Size {
+        Size { width, height }
+    }
+
+    fn symbols(buffer: &Buffer) -> String {
+        buffer.cells.iter().map(|cell| cell.symbol).collect()
+    }
+
+    #[test]
+    fn test_write() {
+        let mut buffer = Buffer::new(size(4, 2));
+        let style = Style::new()
+            .foreground(Color::Red)
+            .attributes(Attributes::BOLD);
+        buffer.write(Point { x: 1, y: 1 }, "abcdef", style);
+        buffer.write(Point { x: 0, y: 5 }, "abcdef", style);
+        assert_eq!(symbols(&buffer), "     abc");
+        assert_eq!(
+            buffer.get(Point { x: 2, y: 1 }),
+            Some(&Cell {
+                symbol: 'b',
+                style,
+                link: None
+            })
+        );
+        assert_eq!(buffer.get(Point { x: 4, y: 1 }), None);
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut buffer = Buffer::new(size(3, 2));
+        buffer.write(Point { x: 0, y: 0 }, "abc", Style::default());
+        buffer.write(Point { x: 0, y: 1 }, "def", Style::default());
+
+        buffer.resize(size(2, 3));
+        assert_eq!(symbols(&buffer), "abde  ");
+    }
+
+    #[test]
+    fn test_diff() {
+        let previous = Buffer::new(size(3, 2));
+        let mut buffer = previous.clone();
+        buffer.write(Point { x: 2, y: 0 }, "a", Style::default());
+        buffer.write(
+            Point { x: 1, y: 1 },
+            " ",
+            Style::new().background(Color::Red),
+        );
+
+        let diff: Vec<_> = buffer
+            .diff(&previous)
+            .map(|(point, cell)| (point, cell.symbol))
+            .collect();
+        assert_eq!(
+            diff,
+            [(Point { x: 2, y: 0 }, 'a'), (Point { x: 1, y: 1 }, ' ')]
+        );
+    }
+
+    #[test]
+    fn test_diff_hyperlinks() {
+        let mut previous = Buffer::new(size(3, 1));
+        let link = previous.add_hyperlink(Hyperlink::new("https://example.com"));
+        previous.write_link(Point { x: 0, y: 0 }, "abc", Style::default(), link);
+
+        let mut buffer = previous.clone();
+        assert_eq!(buffer.diff(&previous).count(), 0);
+
+        // The same reference in a buffer where it refers to another link is a change
+        let mut buffer_links = Buffer::new(size(3, 1));
+        buffer_links.add_hyperlink(Hyperlink::new("https://example.org"));
+        buffer.links = buffer_links.links;
+        assert_eq!(buffer.diff(&previous).count(), 3);
+
+        let other = buffer.add_hyperlink(Hyperlink::new("https://example.com").id("1"));
+        assert_eq!(
+            buffer.add_hyperlink(Hyperlink::new("https://example.com").id("1")),
+            other
+        );
+        buffer.write(Point { x: 1, y: 0 }, "b", Style::default());
+        let diff: Vec<_> = buffer
+            .diff(&previous)
+            .map(|(point, cell)| (point.x, buffer.cell_hyperlink(cell).cloned()))
+            .collect();
+        assert_eq!(
+            diff,
+            [
+                (0, Some(Hyperlink::new("https://example.org"))),
+                (1, None),
+                (2, Some(Hyperlink::new("https://example.org")))
+            ]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! What the terminal supports beyond the basics.
+
+use crate::util::Color;
+use std::env;
+
+/// The features a terminal supports that can't be relied upon everywhere.
+///
+/// Features that are not supported are approximated where possible.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Capabilities {
+    /// Whether underlines can have styles other than a single line and a color of their own.
+    ///
+    /// Otherwise every underline is drawn as a single line and underline colors are ignored.
+    pub styled_underlines: bool,
+    /// The colors that can be shown. Other colors are converted into the nearest one that can.
+    pub color_depth: ColorDepth,
+    /// The terminal multiplexer the program runs in, if any.
+    ///
+    /// Sequences that it would not pass on to the terminal it runs in, such as those for the clipboard, are wrapped in
+    /// a way that makes it pass them on.
+    pub multiplexer: Option<Multiplexer>,
+}
+
+/// How many colors a terminal can show.
+///
+/// The depths are ordered from the fewest to the most colors.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub enum ColorDepth {
+    /// No colors are sent at all, such as when the user asked for it with `NO_COLOR`.
+    Monochrome,
+    /// The 16 named colors.
+    FourBit,
+    /// The 256 colors of [`Color::Byte`].
+    EightBit,
+    /// All colors, including [`Color::Rgb`].
+    #[default]
+    TrueColor,
+}
+
+/// A program that runs terminals inside a terminal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Multiplexer {
+    /// Passing sequences on requires the `allow-passthrough` option.
+    Tmux,
+    Screen,
+}
+
+impl Multiplexer {
+    /// Wraps `sequence` so that the multiplexer passes it on to the terminal it runs in.
+    ///
+    /// `sequence` must not be terminated by `ESC \`.
+    pub(crate) fn wrap(self, sequence: &str) -> String {
+        match self {
+            // Escape bytes are doubled inside
+            Multiplexer::Tmux => format!(
+                "\u{1b}Ptmux;{}\u{1b}\\",
+                sequence.replace('\u{1b}', "\u{1b}\u{1b}")
+            ),
+            // screen cuts off long strings, so the sequence is passed on in pieces
+            Multiplexer::Screen => sequence
+                .as_bytes()
+                .chunks(SCREEN_CHUNK_LENGTH)
+                .map(|chunk| format!("\u{1b}P{}\u{1b}\\", String::from_utf8_lossy(chunk)))
+                .collect(),
+        }
+    }
+}
+
+/// The length of the pieces a sequence is split into for screen, which is below its limit of 768 bytes.
+const SCREEN_CHUNK_LENGTH: usize = 512;
+
+impl ColorDepth {
+    /// Converts `color` into the nearest color of this depth or returns `None` if no colors can be shown.
+    pub fn convert(self, color: Color) -> Option<Color> {
+        match self {
+            ColorDepth::Monochrome => None,
+            ColorDepth::FourBit => Some(color.to_four_bit()),
+            ColorDepth::EightBit => Some(color.to_byte()),
+            ColorDepth::TrueColor => Some(color),
+        }
+    }
+}
+
+impl Capabilities {
+    /// Guesses the capabilities of the terminal the program runs in using environment variables.
+    pub fn detect() -> Self {
+        let var = |name: &str| env::var(name).ok();
+        Self {
+            styled_underlines: detect_styled_underlines(var),
+            color_depth: detect_color_depth(var),
+            multiplexer: detect_multiplexer(var),
+        }
+    }
+}
+
+fn detect_styled_underlines(var: impl Fn(&str) -> Option<String>) -> bool {
+    let term = var("TERM").unwrap_or_default();
+    let term_program = var("TERM_PROGRAM").unwrap_or_default();
+
+    // Multiplexers only pass them on if the terminal they run in supports them
+    if term.starts_with("screen") || term.starts_with("tmux") {
+        return false;
+    }
+
+    // VTE added them in version 0.52
+    let vte_version = var("VTE_VERSION").and_then(|version| version.parse::<u32>().ok());
+    if vte_version.is_some_and(|version| version >= 5200) {
+        return true;
+    }
+
+    matches!(
+        term.as_str(),
+        "xterm-kitty" | "xterm-ghostty" | "wezterm" | "foot" | "foot-extra" | "alacritty"
+    ) || matches!(term_program.as_str(), "WezTerm" | "ghostty")
+}
+
+fn detect_multiplexer(var: impl Fn(&str) -> Option<String>) -> Option<Multiplexer> {
+    // tmux also sets `TERM` to `screen`
+    if var("TMUX").is_some() {
+        Some(Multiplexer::Tmux)
+    } else if var("STY").is_some() {
+        Some(Multiplexer::Screen)
+    } else {
+        None
+    }
+}
+
+fn detect_color_depth(var: impl Fn(&str) -> Option<String>) -> ColorDepth {
+    // See https://no-color.org
+    if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        return ColorDepth::Monochrome;
+    }
+
+    if let Some("truecolor" | "24bit") = var("COLORTERM").as_deref() {
+        return ColorDepth::TrueColor;
+    }
+
+    let term = match var("TERM") {
+        Some(term) => term,
+        // The Windows console does not set it but Windows Terminal supports all colors
+        None if var("WT_SESSION").is_some() => return ColorDepth::TrueColor,
+        None => return ColorDepth::FourBit,
+    };
+    if term == "dumb" {
+        ColorDepth::Monochrome
+    } else if term.ends_with("-direct") || term == "xterm-kitty" || term == "xterm-ghostty" {
+        ColorDepth::TrueColor
+    } else if term.ends_with("256color") {
+        ColorDepth::EightBit
+    } else {
+        ColorDepth::FourBit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(vars: &[(&str, &str)]) -> ColorDepth {
+        detect_color_depth(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_detect_color_depth() {
+        assert_eq!(detect(&[]), ColorDepth::FourBit);
+        assert_eq!(detect(&[("TERM", "xterm")]), ColorDepth::FourBit);
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), ColorDepth::EightBit);
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            detect(&[("TERM", "xterm-direct"), ("NO_COLOR", "1")]),
+            ColorDepth::Monochrome
+        );
+        assert_eq!(
+            detect(&[("TERM", "xterm-direct"), ("NO_COLOR", "")]),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(detect(&[("TERM", "dumb")]), ColorDepth::Monochrome);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            Multiplexer::Tmux.wrap("\x1b]52;c;YQ==\x07"),
+            "\x1bPtmux;\x1b\x1b]52;c;YQ==\x07\x1b\\"
+        );
+        let sequence = "a".repeat(SCREEN_CHUNK_LENGTH + 1);
+        assert_eq!(
+            Multiplexer::Screen.wrap(&sequence),
+            format!("\x1bP{}\x1b\\\x1bPa\x1b\\", "a".repeat(SCREEN_CHUNK_LENGTH))
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Copying and pasting with OSC 52, which goes through the terminal and therefore also works over SSH.
+
+use crate::capabilities::Multiplexer;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Where text is copied to or pasted from.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Selection {
+    /// The clipboard that is usually used with Ctrl+C and Ctrl+V.
+    Clipboard,
+    /// The primary selection on X11 and Wayland, which is the selected text that is pasted with the middle mouse button.
+    Primary,
+}
+
+impl Selection {
+    fn parameter(self) -> char {
+        match self {
+            Selection::Clipboard => 'c',
+            Selection::Primary => 'p',
+        }
+    }
+}
+
+/// Returns the sequence that copies `text` to `selection`.
+pub(crate) fn set_sequence(
+    selection: Selection,
+    text: &str,
+    multiplexer: Option<Multiplexer>,
+) -> String {
+    wrap(
+        &format!(
+            "\u{1b}]52;{};{}\u{7}",
+            selection.parameter(),
+            encode(text.as_bytes())
+        ),
+        multiplexer,
+    )
+}
+
+/// Returns the sequence that asks for the text in `selection`.
+pub(crate) fn query_sequence(selection: Selection, multiplexer: Option<Multiplexer>) -> String {
+    wrap(
+        &format!("\u{1b}]52;{};?\u{7}", selection.parameter()),
+        multiplexer,
+    )
+}
+
+/// Parses the text in a reply to [`query_sequence`], such as `52;c;dGV4dA==`.
+pub(crate) fn parse_reply(osc: &str) -> Option<String> {
+    let (_selection, data) = osc.strip_prefix("52;")?.split_once(';')?;
+    let bytes = decode(data)?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn wrap(sequence: &str, multiplexer: Option<Multiplexer>) -> String {
+    match multiplexer {
+        Some(multiplexer) => multiplexer.wrap(sequence),
+        None => sequence.to_string(),
+    }
+}
+
+/// Encodes `bytes` in base64 with padding.
+fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (index, &byte)| {
+                group | (byte as u32) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (group >> (18 - 6 * index)) & 0b11_1111;
+                encoded.push(BASE64[sextet as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes base64 with or without padding. Returns `None` if `string` is not valid base64.
+fn decode(string: &str) -> Option<Vec<u8>> {
+    let string = string.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(string.len() * 3 / 4);
+    let mut group = 0u32;
+    for (index, byte) in string.bytes().enumerate() {
+        let sextet = BASE64.iter().position(|&digit| digit == byte)? as u32;
+        group = group << 6 | sextet;
+        if index % 4 == 3 {
+            bytes.extend_from_slice(&group.to_be_bytes()[1..]);
+            group = 0;
+        }
+    }
+    match string.len() % 4 {
+        0 => {}
+        2 => bytes.push((group >> 4) as u8),
+        3 => bytes.extend_from_slice(&((group >> 2) as u16).to_be_bytes()),
+        _ => return None,
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        let cases: [(&str, &str); 5] = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foobar😀", "Zm9vYmFy8J+YgA=="),
+        ];
+        for (text, encoded) in cases.iter() {
+            assert_eq!(encode(text.as_bytes()), *encoded);
+            assert_eq!(decode(encoded).unwrap(), text.as_bytes());
+        }
+        assert_eq!(decode("Zm8").unwrap(), b"fo");
+        assert_eq!(decode("Zm9vY"), None);
+        assert_eq!(decode("Zm9v\u{7}"), None);
+    }
+}
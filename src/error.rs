@@ -0,0 +1,52 @@
+//! The error type returned by fallible terminal operations.
+
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading from or writing to the terminal failed.
+    Io(io::Error),
+    /// The output stream is not connected to a terminal.
+    NotTTY,
+    /// The terminal backend failed in a way that is not an I/O error, such as reporting an empty size.
+    ///
+    /// Custom [`Backend`](crate::backend::Backend)s can return their own errors with it.
+    Backend(Box<dyn error::Error + Send + Sync>),
+    /// The options passed to [`Terminal::initialize`](crate::Terminal::initialize) can't be used together.
+    InvalidOptions(&'static str),
+    /// The terminal did not reply to a query in time, possibly because it does not support it.
+    Timeout,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "terminal I/O failed: {}", err),
+            Error::NotTTY => write!(f, "output is not a terminal"),
+            Error::Backend(err) => write!(f, "terminal backend failed: {}", err),
+            Error::InvalidOptions(reason) => write!(f, "invalid terminal options: {}", reason),
+            Error::Timeout => write!(f, "terminal did not reply in time"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::NotTTY => None,
+            Error::Backend(err) => Some(err.as_ref()),
+            Error::InvalidOptions(_) => None,
+            Error::Timeout => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
@@ -1,15 +1,16 @@
 //! Terminal events defined specific to usage.
 
 use crate::util::Point;
+use bitflags::bitflags;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum MouseButton {
     Left,
     Middle,
     Right,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum MouseEventKind {
     ScrollUp,
     ScrollDown,
@@ -19,46 +20,78 @@ pub enum MouseEventKind {
     Release(MouseButton),
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
 pub enum Key {
     Char(char),
-    // Alt(char),
-    //  Ctrl(char),
     Up,
     Down,
     Left,
     Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
     Tab,
+    /// Shift+Tab.
+    BackTab,
     Enter,
     F(u8),
     Backspace,
+    Insert,
+    Delete,
     Esc,
+    /// The null byte, usually sent for Ctrl+Space.
+    Null,
 }
 
-// #[derive(Debug)]
-// pub struct KeyEvent {
-//     pub key: Key,
-//     pub modifier: Option<KeyModifier>,
-// }
+bitflags! {
+    /// The modifier keys that were held down during an event.
+    ///
+    /// Which of these are reported depends on the terminal.
+    /// `SUPER`, `HYPER` and `META` are only reported by terminals that encode modifiers like kitty does.
+    #[derive(Default)]
+    pub struct Modifiers: u8 {
+        const SHIFT = 0b0000_0001;
+        const ALT = 0b0000_0010;
+        const CTRL = 0b0000_0100;
+        const SUPER = 0b0000_1000;
+        const HYPER = 0b0001_0000;
+        const META = 0b0010_0000;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
 
-// #[derive(Debug)]
-// pub enum KeyModifier {
-//     Shift,
-//     Control,
-//     Alt,
-// }
+impl KeyEvent {
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self { key, modifiers }
+    }
+}
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct MouseEvent {
     pub kind: MouseEventKind,
     pub point: Point,
-    // TODO: modifier: Option<KeyModifier> (or bitflags for multipl events)
+    pub modifiers: Modifiers,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Event {
-    Key(Key),
+    Key(KeyEvent),
     Mouse(MouseEvent),
     /// No `Size` included. Call [`crate::Terminal::size`] instead.
     Resize,
+    /// Text that was pasted while bracketed paste mode was enabled.
+    ///
+    /// See [`crate::Terminal::enable_bracketed_paste`].
+    Paste(String),
+    /// The terminal window gained focus. Only reported if enabled using [`crate::TerminalOptions::focus_reporting`].
+    FocusGained,
+    /// The terminal window lost focus. Only reported if enabled using [`crate::TerminalOptions::focus_reporting`].
+    FocusLost,
 }
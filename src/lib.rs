@@ -1,147 +1,1881 @@
+pub mod backend;
+pub mod buffer;
+mod capabilities;
+mod clipboard;
+mod error;
 pub mod event;
+mod options;
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+mod panic;
+mod parser;
+pub mod style;
+#[cfg(any(feature = "crossterm", feature = "termion"))]
 mod sys;
 pub mod util;
 
-use crate::util::{Point, Size};
-use std::io::{self, Write};
+use crate::backend::Backend;
+pub use crate::capabilities::{Capabilities, ColorDepth, Multiplexer};
+pub use crate::clipboard::Selection;
+pub use crate::error::{Error, Result};
+pub use crate::options::{CursorShape, InlineCleanup, MouseCapture, TerminalOptions};
+use crate::{
+    buffer::{Buffer, Cell},
+    event::Event,
+    options::Modes,
+    parser::{Parser, Reply},
+    style::{Attributes, Style, UnderlineStyle},
+    util::{parse_x11, Color, Point, Size},
+};
+use std::time::{Duration, Instant};
 
-// TODO: add `error` to abort program with message?
+/// The backend used by [`Terminal::new`].
+#[cfg(feature = "crossterm")]
+pub type DefaultBackend<'a> = backend::CrosstermBackend<std::io::StdoutLock<'a>>;
+/// The backend used by [`Terminal::new`].
+#[cfg(all(feature = "termion", not(feature = "crossterm")))]
+pub type DefaultBackend<'a> = backend::TermionBackend<std::io::StdoutLock<'a>>;
 
-// TODO: return a result instead of `expect`ing?
-
-// Once https://github.com/rust-lang/rust/pull/78515 is merged, some of this can be changed
 #[derive(Debug)]
-pub struct Terminal<'a> {
-    pub stdout: io::BufWriter<io::StdoutLock<'a>>,
+pub struct Terminal<B: Backend> {
+    backend: B,
+    parser: Parser,
     pub size: Size,
     #[cfg(debug_assertions)]
     pub flush_count: usize,
     initialized: bool,
-    with_mouse: bool,
-    // #[cfg(not(target = "windows"))]
-    // stdin: io::Stdin,
+    modes: Modes,
+    /// Set if the panic hook restores this terminal.
+    #[cfg(any(feature = "crossterm", feature = "termion"))]
+    panic_registration: Option<panic::Registration>,
+    /// What is drawn by the next [`Terminal::present`].
+    buffer: Buffer,
+    /// What was drawn by the last [`Terminal::present`].
+    previous_buffer: Buffer,
+    /// Whether the screen has to be cleared and drawn again entirely on the next [`Terminal::present`].
+    redraw: bool,
+    state: State,
+    capabilities: Capabilities,
+    /// How long to wait for the terminal to reply to a query.
+    reply_timeout: Duration,
+    viewport: Option<Viewport>,
 }
 
-#[derive(Clone, Copy, Debug)]
-#[non_exhaustive] // Prevent instantiation
-pub struct NotTTY;
+/// The lines an inline terminal draws into. See [`TerminalOptions::inline`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Viewport {
+    /// The first line.
+    y: u16,
+    /// The amount of lines, which can be more than fit into the terminal after it was resized.
+    height: u16,
+    cleanup: InlineCleanup,
+}
+
+/// What is known about the current state of the terminal.
+/// It is used to avoid sending escape sequences that would not change anything.
+///
+/// `None` means that it is unknown.
+#[derive(Clone, Copy, Default, Debug)]
+struct State {
+    /// `Some(None)` means that the text has the terminal's default color.
+    foreground: Option<Option<Color>>,
+    /// `Some(None)` means that the terminal's default background color is used.
+    background: Option<Option<Color>>,
+    attributes: Attributes,
+    /// The attributes for which it is known whether they are in `attributes`.
+    known_attributes: Attributes,
+    /// `Some(None)` means that underlines have the color of the text.
+    underline_color: Option<Option<Color>>,
+    cursor: Option<Point>,
+    saved_cursor: Option<Point>,
+}
 
 /// A terminal with an `io::Stdout` inside.
 ///
 /// Every program can have only a single instance for writing.
 /// The standard output stream is locked and no other instance can write.
-impl<'a> Terminal<'a> {
-    pub fn new(stdout: io::StdoutLock<'a>) -> Result<Self, NotTTY> {
-        if !Self::is_tty(&stdout) {
-            return Err(NotTTY);
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+impl<'a> Terminal<DefaultBackend<'a>> {
+    pub fn new(stdout: std::io::StdoutLock<'a>) -> Result<Self> {
+        use std::io::IsTerminal;
+
+        if !stdout.is_terminal() {
+            return Err(Error::NotTTY);
         }
 
-        let writer = io::BufWriter::new(stdout);
+        let mut terminal = Self::with_backend(DefaultBackend::new(stdout))?;
+        terminal.set_capabilities(Capabilities::detect());
+        Ok(terminal)
+    }
+}
 
+impl<B: Backend> Terminal<B> {
+    /// Creates a terminal that uses `backend`.
+    ///
+    /// Unlike [`Terminal::new`], this does not check whether the output is a terminal
+    /// and assumes that it has no special [`Capabilities`].
+    pub fn with_backend(backend: B) -> Result<Self> {
+        let size = backend.size()?;
         Ok(Self {
-            stdout: writer,
-            size: Self::size(),
+            size,
+            backend,
+            parser: Parser::default(),
             #[cfg(debug_assertions)]
             flush_count: 0,
             initialized: false,
-            with_mouse: false
-            // #[cfg(not(target = "windows"))]
-            // stdin: io::stdin(),
+            modes: Modes::empty(),
+            #[cfg(any(feature = "crossterm", feature = "termion"))]
+            panic_registration: None,
+            buffer: Buffer::new(size),
+            previous_buffer: Buffer::new(size),
+            redraw: true,
+            state: State::default(),
+            capabilities: Capabilities::default(),
+            reply_timeout: Duration::from_secs(1),
+            viewport: None,
         })
     }
 
-    pub fn write(&mut self, string: &str) {
-        self.stdout.write_all(string.as_bytes()).unwrap();
+    pub fn backend(&self) -> &B {
+        &self.backend
     }
 
-    pub fn write_bytes(&mut self, bytes: &[u8]) {
-        self.stdout.write_all(bytes).unwrap();
+    /// Returns the backend for direct access.
+    ///
+    /// Because the terminal can't know what is done with it, it no longer assumes anything about
+    /// the current colors, attributes and cursor position afterwards.
+    pub fn backend_mut(&mut self) -> &mut B {
+        self.state = State::default();
+        &mut self.backend
     }
 
-    pub fn flush(&mut self) {
-        self.stdout.flush().unwrap();
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
 
-        #[cfg(debug_assertions)]
-        {
-            if self.initialized {
-                self.flush_count += 1;
-                self.save_cursor_point();
-                self.set_cursor(Point { x: 0, y: 0 });
-                let flush_count = self.flush_count;
-                self.write(&format!("Flush count: {}", flush_count));
-                self.restore_cursor_point();
+    /// Overrides what the terminal is assumed to support.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// Returns the color depth that colors are converted to.
+    pub fn color_depth(&self) -> ColorDepth {
+        self.capabilities.color_depth
+    }
+
+    /// Overrides the color depth that colors are converted to.
+    pub fn set_color_depth(&mut self, color_depth: ColorDepth) {
+        self.capabilities.color_depth = color_depth;
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Returns the buffer to draw into. What is drawn is shown by [`Terminal::present`].
+    ///
+    /// The buffer keeps its content after presenting and is resized along with the terminal.
+    pub fn buffer_mut(&mut self) -> &mut Buffer {
+        &mut self.buffer
+    }
+
+    /// Returns the point on the screen where the top left corner of the buffer is drawn.
+    ///
+    /// It is the top left corner of the screen unless the terminal is [inline](TerminalOptions::inline).
+    pub fn viewport_origin(&self) -> Point {
+        Point {
+            x: 0,
+            y: self.viewport.map_or(0, |viewport| viewport.y),
+        }
+    }
+
+    /// Returns the size of what is drawn by [`Terminal::present`].
+    fn viewport_size(&self) -> Size {
+        match self.viewport {
+            Some(viewport) => Size {
+                width: self.size.width,
+                height: viewport.height.min(self.size.height),
+            },
+            None => self.size,
+        }
+    }
+
+    /// Draws what changed in [`Terminal::buffer_mut`] since the last call and flushes.
+    pub fn present(&mut self) -> Result<()> {
+        let origin = self.viewport_origin();
+        if self.redraw {
+            self.reset_colors()?;
+            self.clear()?;
+            self.redraw = false;
+        }
+
+        let changes: Vec<(Point, Cell)> = self
+            .buffer
+            .diff(&self.previous_buffer)
+            .map(|(point, cell)| (point, *cell))
+            .collect();
+        let mut link = None;
+        for (point, cell) in &changes {
+            let point = Point {
+                x: point.x,
+                y: origin.y + point.y,
+            };
+            self.move_cursor_to(point)?;
+            self.set_style(cell.style)?;
+            if cell.link != link {
+                match self.buffer.cell_hyperlink(cell).cloned() {
+                    Some(hyperlink) => {
+                        self.start_hyperlink(&hyperlink.url, hyperlink.id.as_deref())?
+                    }
+                    None => self.end_hyperlink()?,
+                }
+                link = cell.link;
             }
+
+            let mut bytes = [0; 4];
+            self.backend
+                .write_all(cell.symbol.encode_utf8(&mut bytes).as_bytes())?;
+            self.state.cursor = self.cursor_after(point, 1);
         }
+        if link.is_some() {
+            self.end_hyperlink()?;
+        }
+        if !changes.is_empty() {
+            self.reset_colors()?;
+        }
+
+        self.previous_buffer.clone_from(&self.buffer);
+
+        self.flush()
     }
 
-    fn set_panic_hook(with_mouse: bool) {
-        use std::panic;
+    /// Moves the cursor to `point` using the shortest escape sequence.
+    fn move_cursor_to(&mut self, point: Point) -> Result<()> {
+        match self.state.cursor {
+            Some(cursor) if cursor.y == point.y && point.x == cursor.x + 1 => {
+                self.move_cursor_right()
+            }
+            Some(cursor) if cursor.y == point.y && point.x > cursor.x => {
+                self.move_cursor_right_by(point.x - cursor.x)
+            }
+            Some(cursor) if cursor.y == point.y => self.set_cursor_x(point.x),
+            Some(cursor) if point.x == 0 && point.y == cursor.y + 1 => self.next_line(),
+            _ => self.set_screen_cursor(point),
+        }
+    }
 
-        let current_panic_hook = panic::take_hook();
+    /// Returns where the cursor is after writing `cells` cells at `point`.
+    fn cursor_after(&self, point: Point, cells: usize) -> Option<Point> {
+        let x = point.x as usize + cells;
+        // At the end of a row, where the cursor ends up differs between terminals
+        if x < self.size.width as usize {
+            Some(Point {
+                x: x as u16,
+                y: point.y,
+            })
+        } else {
+            None
+        }
+    }
 
-        panic::set_hook(Box::new(move |panic_info| {
-            let stdout = io::stdout();
+    pub fn write(&mut self, string: &str) -> Result<()> {
+        self.backend.write_all(string.as_bytes())?;
 
-            let mut terminal = Terminal::new(stdout.lock()).unwrap();
-            terminal.initialized = true;
-            terminal.with_mouse = with_mouse;
+        if string.bytes().any(|byte| byte.is_ascii_control()) {
+            // This might be an escape sequence that changes anything
+            self.state = State::default();
+        } else if string.is_ascii() {
+            self.state.cursor = self
+                .state
+                .cursor
+                .and_then(|cursor| self.cursor_after(cursor, string.len()));
+        } else {
+            // Without knowing the width of the characters
+            self.state.cursor = None;
+        }
 
-            terminal.deinitialize();
-            terminal.flush(); // Flush so that we can see the following output in the normal view
+        Ok(())
+    }
 
-            current_panic_hook(panic_info);
-        }));
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.backend.write_all(bytes)?;
+        self.state = State::default();
+        Ok(())
     }
 
-    /// Makes this terminal suitable for drawing and input.
+    pub fn flush(&mut self) -> Result<()> {
+        self.backend.flush()?;
+
+        #[cfg(debug_assertions)]
+        {
+            // Only on the alternate screen, where it does not overwrite what the shell printed
+            if self.initialized && self.modes.contains(Modes::ALTERNATE_SCREEN) {
+                self.flush_count += 1;
+                self.save_cursor_point()?;
+                self.set_screen_cursor(self.viewport_origin())?;
+                let flush_count = self.flush_count;
+                self.write(&format!("Flush count: {}", flush_count))?;
+                self.restore_cursor_point()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records whether `mode` is in effect so that it can be undone later.
+    fn set_mode(&mut self, mode: Modes, enabled: bool) {
+        self.modes.set(mode, enabled);
+        #[cfg(any(feature = "crossterm", feature = "termion"))]
+        if let Some(registration) = &self.panic_registration {
+            registration.set_modes(self.modes);
+        }
+    }
+
+    /// Makes this terminal suitable for drawing and input as described by `options`.
     ///
     /// Note that this does not do anything until [`flush`] is used.
-    pub fn initialize(&mut self, title: Option<&str>, with_mouse: bool) {
-        self.enter_alternate_dimension();
-        self.enable_raw_mode();
-        self.hide_cursor();
-
-        if let Some(title) = title {
-            self.set_title(title);
+    pub fn initialize(&mut self, options: &TerminalOptions) -> Result<()> {
+        // Otherwise the reply to the cursor position query only arrives once the user presses Enter
+        if options.inline.is_some() && !options.raw_mode && !self.modes.contains(Modes::RAW_MODE) {
+            return Err(Error::InvalidOptions(
+                "an inline terminal needs raw mode to query the cursor position",
+            ));
         }
 
-        if with_mouse {
-            self.enable_mouse_capture();
+        // This is done first so that the panic hook knows the mode of the terminal from before
+        #[cfg(any(feature = "crossterm", feature = "termion"))]
+        if options.panic_hook {
+            panic::install_hook();
+            self.panic_registration = Some(panic::Registration::new());
         }
 
-        Self::set_panic_hook(with_mouse);
+        if options.alternate_screen {
+            self.enter_alternate_dimension()?;
+        }
+        if options.raw_mode {
+            self.enable_raw_mode()?;
+        }
+        if options.hide_cursor {
+            self.hide_cursor()?;
+        }
+        if let Some(title) = &options.title {
+            // Save the current title on the title stack so that it can be restored
+            self.write("\u{1b}[22;0t")?;
+            self.set_mode(Modes::SAVED_TITLE, true);
+            self.set_title(title)?;
+        }
+        if let Some(capture) = options.mouse_capture {
+            self.enable_mouse_capture(capture)?;
+        }
+        if options.bracketed_paste {
+            self.enable_bracketed_paste()?;
+        }
+        if options.focus_reporting {
+            self.enable_focus_reporting()?;
+        }
 
+        // This is set before the viewport is reserved, which can fail if the terminal does not reply,
+        // so that the modes above are still undone then
         self.initialized = true;
+
+        if let Some(height) = options.inline {
+            self.enter_inline_viewport(height, options.inline_cleanup)?;
+        }
+
+        Ok(())
     }
 
     /// Deinitializes the terminal back into its normal state.
     ///
+    /// Only what is still in effect from [`initialize`](Self::initialize) or the other methods is undone.
+    /// If the terminal is not initialized, nothing happens.
+    ///
+    /// This also happens automatically when the terminal is dropped.
+    ///
     /// Note that this does not do anything until [`flush`] is used.
-    pub fn deinitialize(&mut self) {
+    pub fn deinitialize(&mut self) -> Result<()> {
         if !self.initialized {
-            panic!("terminal is not initialized");
+            return Ok(());
         }
 
-        self.exit_alternate_dimension();
-        self.disable_raw_mode();
-        self.show_cursor();
-
-        if self.with_mouse {
-            self.disable_mouse_capture();
+        if let Some(viewport) = self.viewport {
+            self.exit_inline_viewport(viewport)?;
+        }
+        if self.modes.contains(Modes::FOCUS_REPORTING) {
+            self.disable_focus_reporting()?;
+        }
+        if self.modes.contains(Modes::BRACKETED_PASTE) {
+            self.disable_bracketed_paste()?;
+        }
+        if self.modes.contains(Modes::MOUSE_CAPTURE) {
+            self.disable_mouse_capture()?;
+        }
+        if self.modes.contains(Modes::SAVED_TITLE) {
+            self.write("\u{1b}[23;0t")?;
+            self.set_mode(Modes::SAVED_TITLE, false);
+        }
+        if self.modes.contains(Modes::CURSOR_SHAPE) {
+            self.set_cursor_shape(CursorShape::Default)?;
+        }
+        if self.modes.contains(Modes::HIDDEN_CURSOR) {
+            self.show_cursor()?;
+        }
+        if self.modes.contains(Modes::RAW_MODE) {
+            self.disable_raw_mode()?;
+        }
+        if self.modes.contains(Modes::ALTERNATE_SCREEN) {
+            self.exit_alternate_dimension()?;
         }
 
         self.initialized = false;
+
+        Ok(())
+    }
+
+    /// Reserves `height` lines at the cursor to draw into. See [`TerminalOptions::inline`].
+    fn enter_inline_viewport(&mut self, height: u16, cleanup: InlineCleanup) -> Result<()> {
+        let cursor = self.cursor_position()?;
+        // The reply can't be trusted to be on the screen
+        let cursor_y = cursor.y.min(self.size.height.saturating_sub(1));
+        let height = height.min(self.size.height).max(1);
+        let start = if cursor.x > 0 {
+            cursor_y.saturating_add(1)
+        } else {
+            cursor_y
+        };
+        let end = start.saturating_add(height);
+
+        // Moving down past the last line scrolls the content up
+        let newlines = end - 1 - cursor_y;
+        self.write(&"\n".repeat(newlines as usize))?;
+        let scrolled = end.saturating_sub(self.size.height);
+
+        self.viewport = Some(Viewport {
+            y: start.saturating_sub(scrolled),
+            height,
+            cleanup,
+        });
+        self.resize_buffers();
+        self.publish_viewport();
+        Ok(())
+    }
+
+    /// Leaves the lines of the viewport as `viewport.cleanup` says and then draws to the whole screen again.
+    fn exit_inline_viewport(&mut self, viewport: Viewport) -> Result<()> {
+        self.reset_colors()?;
+        match viewport.cleanup {
+            InlineCleanup::Keep => {
+                let last_line = (viewport.y + self.viewport_size().height).saturating_sub(1);
+                self.set_screen_cursor(Point { x: 0, y: last_line })?;
+                self.write("\r\n")?;
+            }
+            InlineCleanup::Clear => {
+                self.set_screen_cursor(self.viewport_origin())?;
+                self.clear_from_cursor_to_end()?;
+            }
+        }
+
+        self.viewport = None;
+        self.resize_buffers();
+        self.publish_viewport();
+        Ok(())
+    }
+
+    /// Tells the panic hook where the viewport is, so that it leaves it like [`Terminal::deinitialize`] would.
+    fn publish_viewport(&self) {
+        #[cfg(any(feature = "crossterm", feature = "termion"))]
+        if let Some(registration) = &self.panic_registration {
+            let height = self.viewport_size().height;
+            registration.set_viewport(
+                self.viewport
+                    .map(|viewport| Viewport { height, ..viewport }),
+            );
+        }
+    }
+
+    /// Resizes the buffers to the size of the viewport and makes the next [`Terminal::present`] draw everything.
+    fn resize_buffers(&mut self) {
+        let size = self.viewport_size();
+        self.buffer.resize(size);
+        self.previous_buffer = Buffer::new(size);
+        self.redraw = true;
     }
 
     pub fn contains(&self, point: Point) -> bool {
-        point.x > 0 && point.x < self.size.width && point.y < self.size.height && point.y > 0
+        let size = self.viewport_size();
+        point.x > 0 && point.x < size.width && point.y < size.height && point.y > 0
+    }
+
+    pub fn enter_alternate_dimension(&mut self) -> Result<()> {
+        self.backend.enter_alternate_dimension()?;
+        self.set_mode(Modes::ALTERNATE_SCREEN, true);
+        self.state = State::default();
+        Ok(())
+    }
+    pub fn exit_alternate_dimension(&mut self) -> Result<()> {
+        self.backend.exit_alternate_dimension()?;
+        self.set_mode(Modes::ALTERNATE_SCREEN, false);
+        self.state = State::default();
+        Ok(())
+    }
+
+    pub fn set_title(&mut self, title: &str) -> Result<()> {
+        self.backend.set_title(title)
+    }
+
+    pub fn enable_raw_mode(&mut self) -> Result<()> {
+        self.backend.enable_raw_mode()?;
+        self.set_mode(Modes::RAW_MODE, true);
+        Ok(())
+    }
+    pub fn disable_raw_mode(&mut self) -> Result<()> {
+        self.backend.disable_raw_mode()?;
+        self.set_mode(Modes::RAW_MODE, false);
+        Ok(())
+    }
+
+    pub fn enable_mouse_capture(&mut self, capture: MouseCapture) -> Result<()> {
+        self.backend.enable_mouse_capture(capture)?;
+        self.set_mode(Modes::MOUSE_CAPTURE, true);
+        Ok(())
+    }
+    pub fn disable_mouse_capture(&mut self) -> Result<()> {
+        self.backend.disable_mouse_capture()?;
+        self.set_mode(Modes::MOUSE_CAPTURE, false);
+        Ok(())
+    }
+
+    /// Makes pasted text arrive as a single [`Event::Paste`] instead of separate key events.
+    pub fn enable_bracketed_paste(&mut self) -> Result<()> {
+        self.backend.enable_bracketed_paste()?;
+        self.set_mode(Modes::BRACKETED_PASTE, true);
+        Ok(())
+    }
+    pub fn disable_bracketed_paste(&mut self) -> Result<()> {
+        self.backend.disable_bracketed_paste()?;
+        self.set_mode(Modes::BRACKETED_PASTE, false);
+        Ok(())
+    }
+
+    /// Makes the terminal report [`Event::FocusGained`] and [`Event::FocusLost`].
+    pub fn enable_focus_reporting(&mut self) -> Result<()> {
+        self.backend.enable_focus_reporting()?;
+        self.set_mode(Modes::FOCUS_REPORTING, true);
+        Ok(())
+    }
+    pub fn disable_focus_reporting(&mut self) -> Result<()> {
+        self.backend.disable_focus_reporting()?;
+        self.set_mode(Modes::FOCUS_REPORTING, false);
+        Ok(())
+    }
+
+    /// Sets the shape of the cursor with DECSCUSR.
+    ///
+    /// The default shape is restored on deinitialization and by the panic hook.
+    pub fn set_cursor_shape(&mut self, shape: CursorShape) -> Result<()> {
+        self.backend.set_cursor_shape(shape)?;
+        self.set_mode(Modes::CURSOR_SHAPE, shape != CursorShape::Default);
+        Ok(())
+    }
+
+    pub fn show_cursor(&mut self) -> Result<()> {
+        self.backend.show_cursor()?;
+        self.set_mode(Modes::HIDDEN_CURSOR, false);
+        Ok(())
+    }
+    pub fn hide_cursor(&mut self) -> Result<()> {
+        self.backend.hide_cursor()?;
+        self.set_mode(Modes::HIDDEN_CURSOR, true);
+        Ok(())
+    }
+
+    /// Blocks until an event is read. It also sets the new size if the terminal has been resized, hence a mutable borrow of `self` is required.
+    pub fn read_event(&mut self) -> Result<Event> {
+        loop {
+            if let Some(event) = self.next_event(None)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Reads an event if one arrives within `timeout`.
+    ///
+    /// With a `timeout` of zero, this only returns input that already arrived and does not wait.
+    pub fn poll_event(&mut self, timeout: Duration) -> Result<Option<Event>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            // Input is read at least once, even if there is no time left
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if let Some(event) = self.next_event(Some(remaining))? {
+                return Ok(Some(event));
+            }
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Returns the next event, reading input for at most `timeout` if there is none yet.
+    fn next_event(&mut self, timeout: Option<Duration>) -> Result<Option<Event>> {
+        if let Some(event) = self.parser.next() {
+            return Ok(Some(event));
+        }
+
+        if self.read_input(timeout)? {
+            return Ok(Some(Event::Resize));
+        }
+
+        Ok(self.parser.next())
+    }
+
+    /// Reads input for at most `timeout` and decodes it. Returns whether the terminal has been resized.
+    fn read_input(&mut self, timeout: Option<Duration>) -> Result<bool> {
+        let mut buffer = [0; 1024];
+        let count = self.backend.read_input(&mut buffer, timeout)?;
+
+        self.parser.expire_cursor_positions(Instant::now());
+        self.parser.advance(&buffer[..count], count == buffer.len());
+
+        let size = self.backend.size()?;
+        if size != self.size {
+            self.size = size;
+            if let Some(viewport) = &mut self.viewport {
+                // Keep the viewport on the screen. Content the terminal reflows is not followed.
+                viewport.y = viewport.y.min(size.height.saturating_sub(viewport.height));
+            }
+            self.resize_buffers();
+            self.publish_viewport();
+            // The terminal might have moved the cursor while reflowing its content
+            self.state.cursor = None;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Sets how long queries wait for the terminal to reply before failing with [`Error::Timeout`].
+    ///
+    /// The default is one second. Terminals that don't support a query never reply to it.
+    pub fn set_reply_timeout(&mut self, timeout: Duration) {
+        self.reply_timeout = timeout;
+    }
+
+    /// Flushes and then reads input until the terminal sends a reply that `parse` accepts.
+    ///
+    /// Events that arrive in the meantime are kept for [`Terminal::read_event`]. Other replies are dropped.
+    fn read_reply<T>(&mut self, mut parse: impl FnMut(&Reply) -> Option<T>) -> Result<T> {
+        // Replies that are left over, such as ones that arrived after a timeout, don't belong to this query
+        while self.parser.next_reply().is_some() {}
+        self.flush()?;
+
+        let deadline = Instant::now() + self.reply_timeout;
+        loop {
+            while let Some(reply) = self.parser.next_reply() {
+                if let Some(value) = parse(&reply) {
+                    return Ok(value);
+                }
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Timeout);
+            }
+            if self.read_input(Some(deadline - now))? {
+                self.parser.push(Event::Resize);
+            }
+        }
+    }
+
+    /// Like [`Terminal::read_reply`], but for replies that are OSC sequences.
+    fn read_osc_reply<T>(&mut self, mut parse: impl FnMut(&str) -> Option<T>) -> Result<T> {
+        self.parser.expect_osc(true);
+        let reply = self.read_reply(|reply| match reply {
+            Reply::Osc(osc) => parse(osc),
+            _ => None,
+        });
+        self.parser.expect_osc(false);
+        reply
+    }
+
+    /// Sends the OSC color query `number` and reads the color in the reply.
+    fn query_color(&mut self, number: &str) -> Result<Color> {
+        write!(self.backend, "\u{1b}]{};?\u{7}", number)?;
+        let prefix = format!("{};", number);
+        self.read_osc_reply(|osc| parse_x11(osc.strip_prefix(&prefix)?))
+    }
+
+    /// Asks the terminal where the cursor is with CSI 6n.
+    ///
+    /// Like the other cursor methods, the position is relative to the [viewport origin](Terminal::viewport_origin).
+    /// A cursor above an inline viewport is reported to be on its first line.
+    ///
+    /// Events that arrive while waiting for the reply are kept for [`Terminal::read_event`].
+    pub fn cursor_position(&mut self) -> Result<Point> {
+        self.backend.write_all(b"\x1b[6n")?;
+        self.parser.expect_cursor_position();
+        let point = self.read_reply(|reply| match reply {
+            Reply::CursorPosition(point) => Some(*point),
+            _ => None,
+        });
+        if let Err(Error::Timeout) = point {
+            // A reply that arrives a bit late is still decoded as one instead of as a key,
+            // but one that never arrives must not swallow that key forever
+            self.parser
+                .give_up_cursor_position(Instant::now() + self.reply_timeout);
+        }
+        let point = point?;
+        self.state.cursor = Some(point);
+        let origin = self.viewport_origin();
+        Ok(Point {
+            x: point.x,
+            y: point.y.saturating_sub(origin.y),
+        })
+    }
+
+    /// Queries the color the terminal shows for [`Color::Byte`] with `index`.
+    ///
+    /// The first 16 indices are the colors the 4-bit colors are shown in.
+    pub fn query_palette_color(&mut self, index: u8) -> Result<Color> {
+        self.query_color(&format!("4;{}", index))
+    }
+
+    /// Queries the terminal's default foreground text color.
+    pub fn query_foreground_color(&mut self) -> Result<Color> {
+        self.query_color("10")
+    }
+
+    /// Queries the terminal's default background color.
+    ///
+    /// This can tell whether the terminal has a dark or a light theme.
+    pub fn query_background_color(&mut self) -> Result<Color> {
+        self.query_color("11")
+    }
+
+    /// Queries the terminal's cursor color.
+    pub fn query_cursor_color(&mut self) -> Result<Color> {
+        self.query_color("12")
+    }
+
+    /// Sets the cursor to the top left corner of the viewport.
+    pub fn reset_cursor(&mut self) -> Result<()> {
+        if self.viewport.is_some() {
+            return self.set_screen_cursor(self.viewport_origin());
+        }
+        if self.state.cursor == Some(Point::default()) {
+            return Ok(());
+        }
+        self.backend.reset_cursor()?;
+        self.state.cursor = Some(Point::default());
+        Ok(())
+    }
+
+    /// Sets the cursor to `point`, which is relative to the [viewport origin](Terminal::viewport_origin).
+    ///
+    /// If possible, try to use the `move_cursor_{}_by` and `move_cursor_{}` methods instead for single operations.
+    pub fn set_cursor(&mut self, point: Point) -> Result<()> {
+        let origin = self.viewport_origin();
+        self.set_screen_cursor(Point {
+            x: point.x,
+            y: origin.y.saturating_add(point.y),
+        })
+    }
+
+    /// Sets the cursor to `point` on the screen regardless of the viewport.
+    fn set_screen_cursor(&mut self, point: Point) -> Result<()> {
+        if self.state.cursor == Some(point) {
+            return Ok(());
+        }
+        self.backend.set_cursor(point)?;
+        self.state.cursor = Some(point);
+        Ok(())
+    }
+
+    /// Moves the cursor using `command`, unless the cursor is known to already be where it would end up.
+    ///
+    /// `target` returns where the cursor ends up given where it is now.
+    fn move_cursor(
+        &mut self,
+        target: impl FnOnce(Point, Size) -> Point,
+        command: impl FnOnce(&mut B) -> Result<()>,
+    ) -> Result<()> {
+        let size = self.size;
+        let target = self.state.cursor.map(|cursor| target(cursor, size));
+        if target.is_some() && target == self.state.cursor {
+            return Ok(());
+        }
+        command(&mut self.backend)?;
+        self.state.cursor = target;
+        Ok(())
+    }
+
+    /// Sets the cursor X-coordinate to `x`.
+    pub fn set_cursor_x(&mut self, x: u16) -> Result<()> {
+        self.move_cursor(
+            |cursor, _| Point { x, ..cursor },
+            |backend| backend.set_cursor_x(x),
+        )
+    }
+
+    /// Sets the cursor Y-coordinate to `y`, which is relative to the [viewport origin](Terminal::viewport_origin).
+    pub fn set_cursor_y(&mut self, y: u16) -> Result<()> {
+        let y = self.viewport_origin().y.saturating_add(y);
+        self.move_cursor(
+            |cursor, _| Point { y, ..cursor },
+            |backend| backend.set_cursor_y(y),
+        )
+    }
+
+    // The cursor stops at the edges of the terminal.
+    // Moving by 0 cells is skipped because terminals treat it like moving by 1 cell.
+
+    pub fn move_cursor_up_by(&mut self, cells: u16) -> Result<()> {
+        if cells == 0 {
+            return Ok(());
+        }
+        self.move_cursor(
+            |cursor, _| Point {
+                y: cursor.y.saturating_sub(cells),
+                ..cursor
+            },
+            |backend| backend.move_cursor_up_by(cells),
+        )
+    }
+    pub fn move_cursor_down_by(&mut self, cells: u16) -> Result<()> {
+        if cells == 0 {
+            return Ok(());
+        }
+        self.move_cursor(
+            |cursor, size| Point {
+                y: cursor
+                    .y
+                    .saturating_add(cells)
+                    .min(size.height.saturating_sub(1)),
+                ..cursor
+            },
+            |backend| backend.move_cursor_down_by(cells),
+        )
+    }
+    pub fn move_cursor_left_by(&mut self, cells: u16) -> Result<()> {
+        if cells == 0 {
+            return Ok(());
+        }
+        self.move_cursor(
+            |cursor, _| Point {
+                x: cursor.x.saturating_sub(cells),
+                ..cursor
+            },
+            |backend| backend.move_cursor_left_by(cells),
+        )
+    }
+    pub fn move_cursor_right_by(&mut self, cells: u16) -> Result<()> {
+        if cells == 0 {
+            return Ok(());
+        }
+        self.move_cursor(
+            |cursor, size| Point {
+                x: cursor
+                    .x
+                    .saturating_add(cells)
+                    .min(size.width.saturating_sub(1)),
+                ..cursor
+            },
+            |backend| backend.move_cursor_right_by(cells),
+        )
+    }
+
+    pub fn move_cursor_up(&mut self) -> Result<()> {
+        self.move_cursor(
+            |cursor, _| Point {
+                y: cursor.y.saturating_sub(1),
+                ..cursor
+            },
+            |backend| backend.move_cursor_up(),
+        )
+    }
+    pub fn move_cursor_down(&mut self) -> Result<()> {
+        self.move_cursor(
+            |cursor, size| Point {
+                y: (cursor.y + 1).min(size.height.saturating_sub(1)),
+                ..cursor
+            },
+            |backend| backend.move_cursor_down(),
+        )
+    }
+    pub fn move_cursor_left(&mut self) -> Result<()> {
+        self.move_cursor(
+            |cursor, _| Point {
+                x: cursor.x.saturating_sub(1),
+                ..cursor
+            },
+            |backend| backend.move_cursor_left(),
+        )
+    }
+    pub fn move_cursor_right(&mut self) -> Result<()> {
+        self.move_cursor(
+            |cursor, size| Point {
+                x: (cursor.x + 1).min(size.width.saturating_sub(1)),
+                ..cursor
+            },
+            |backend| backend.move_cursor_right(),
+        )
+    }
+
+    pub fn next_line(&mut self) -> Result<()> {
+        self.move_cursor(
+            |cursor, size| Point {
+                x: 0,
+                y: (cursor.y + 1).min(size.height.saturating_sub(1)),
+            },
+            |backend| backend.next_line(),
+        )
+    }
+    pub fn previous_line(&mut self) -> Result<()> {
+        self.move_cursor(
+            |cursor, _| Point {
+                x: 0,
+                y: cursor.y.saturating_sub(1),
+            },
+            |backend| backend.previous_line(),
+        )
+    }
+
+    pub fn save_cursor_point(&mut self) -> Result<()> {
+        self.backend.save_cursor_point()?;
+        self.state.saved_cursor = self.state.cursor;
+        Ok(())
+    }
+    pub fn restore_cursor_point(&mut self) -> Result<()> {
+        self.backend.restore_cursor_point()?;
+        // Some terminals restore the colors and attributes as well
+        self.state = State {
+            cursor: self.state.saved_cursor,
+            ..State::default()
+        };
+        Ok(())
+    }
+
+    /// Sets the color of the text that follows.
+    ///
+    /// `color` is converted into the nearest color of the [`ColorDepth`] in effect.
+    pub fn set_foreground_color(&mut self, color: Color) -> Result<()> {
+        let color = match self.capabilities.color_depth.convert(color) {
+            Some(color) if self.state.foreground != Some(Some(color)) => color,
+            _ => return Ok(()),
+        };
+        self.backend.set_foreground_color(color)?;
+        self.state.foreground = Some(Some(color));
+        Ok(())
+    }
+    /// Makes the text that follows have the terminal's default color.
+    ///
+    /// Unlike [`Terminal::reset_foreground_color`], this does not change what the default color is.
+    pub fn set_default_foreground_color(&mut self) -> Result<()> {
+        if self.state.foreground == Some(None) {
+            return Ok(());
+        }
+        self.backend.reset_foreground_color()?;
+        self.state.foreground = Some(None);
+        Ok(())
+    }
+    /// Sets the color behind the text that follows.
+    ///
+    /// `color` is converted into the nearest color of the [`ColorDepth`] in effect.
+    pub fn set_background_color(&mut self, color: Color) -> Result<()> {
+        let color = match self.capabilities.color_depth.convert(color) {
+            Some(color) if self.state.background != Some(Some(color)) => color,
+            _ => return Ok(()),
+        };
+        self.backend.set_background_color(color)?;
+        self.state.background = Some(Some(color));
+        Ok(())
+    }
+    /// Makes the text that follows have the terminal's default background color.
+    ///
+    /// Unlike [`Terminal::reset_background_color`], this does not change what the default color is.
+    pub fn set_default_background_color(&mut self) -> Result<()> {
+        if self.state.background == Some(None) {
+            return Ok(());
+        }
+        self.backend.reset_background_color()?;
+        self.state.background = Some(None);
+        Ok(())
+    }
+
+    //
+    // TODO for the following methods: Do they work on Windows?
+    //
+
+    // Reference: https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Operating-System-Commands
+
+    /// Returns the RGB value the terminal shows `color` in.
+    ///
+    /// Colors other than [`Color::Rgb`] are looked up in the terminal's palette.
+    fn resolve_color(&mut self, color: Color) -> Result<(u8, u8, u8)> {
+        let index = match color {
+            Color::Rgb { r, g, b } => return Ok((r, g, b)),
+            Color::Byte(byte) => byte,
+            color => color.four_bit_index().unwrap(),
+        };
+        match self.query_palette_color(index) {
+            Ok(color) => Ok(color.rgb()),
+            // The terminal can't be asked, so assume that it uses the default palette
+            Err(Error::Timeout) => Ok(color.rgb()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Sets the OSC color `number` to `color`.
+    fn change_color(&mut self, number: u8, color: Color) -> Result<()> {
+        let (r, g, b) = self.resolve_color(color)?;
+        write!(
+            self.backend,
+            "\u{1b}]{};#{:02x}{:02x}{:02x}\u{7}",
+            number, r, g, b
+        )?;
+        Ok(())
+    }
+
+    /// Changes the terminal's foreground text color to `color`.
+    ///
+    /// Colors other than [`Color::Rgb`] are resolved to the RGB value they have in the terminal's palette,
+    /// which might take until the [reply timeout](Terminal::set_reply_timeout) if the terminal does not tell it.
+    pub fn change_foreground_color(&mut self, color: Color) -> Result<()> {
+        self.change_color(10, color)
+    }
+    pub fn reset_foreground_color(&mut self) -> Result<()> {
+        self.write("\u{1b}]110\u{7}")
+    }
+
+    /// Changes the terminal's background color to `color`.
+    ///
+    /// See [`Terminal::change_foreground_color`] for how `color` is resolved.
+    pub fn change_background_color(&mut self, color: Color) -> Result<()> {
+        self.change_color(11, color)
+    }
+    pub fn reset_background_color(&mut self) -> Result<()> {
+        self.write("\u{1b}]111\u{7}")
+    }
+
+    /// Changes the terminal's cursor color to `color`.
+    ///
+    /// See [`Terminal::change_foreground_color`] for how `color` is resolved.
+    pub fn change_cursor_color(&mut self, color: Color) -> Result<()> {
+        self.change_color(12, color)
+    }
+    pub fn reset_cursor_color(&mut self) -> Result<()> {
+        self.write("\u{1b}]112\u{7}")
+    }
+
+    /// Makes the text that follows a hyperlink to `url` with OSC 8 until [`Terminal::end_hyperlink`].
+    ///
+    /// Text with the same `id` and `url` is treated as one link, even if it is interrupted. See [`Hyperlink::id`](buffer::Hyperlink::id).
+    /// Characters that are not allowed in URLs are percent-encoded and ones that are not allowed in `id` are removed.
+    pub fn start_hyperlink(&mut self, url: &str, id: Option<&str>) -> Result<()> {
+        let mut sequence = String::from("\u{1b}]8;");
+        if let Some(id) = id {
+            sequence.push_str("id=");
+            sequence.extend(
+                id.chars()
+                    .filter(|char| matches!(char, '!'..='~') && *char != ':' && *char != ';'),
+            );
+        }
+        sequence.push(';');
+        for byte in url.bytes() {
+            match byte {
+                b'!'..=b'~' => sequence.push(byte as char),
+                byte => sequence.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        sequence.push('\u{7}');
+        self.backend.write_all(sequence.as_bytes())?;
+        Ok(())
+    }
+
+    /// Ends the hyperlink started with [`Terminal::start_hyperlink`].
+    pub fn end_hyperlink(&mut self) -> Result<()> {
+        self.backend.write_all(b"\x1b]8;;\x07")?;
+        Ok(())
+    }
+
+    /// Copies `text` to the system clipboard. See [`Terminal::set_selection`].
+    pub fn set_clipboard(&mut self, text: &str) -> Result<()> {
+        self.set_selection(Selection::Clipboard, text)
+    }
+
+    /// Copies `text` to `selection` with OSC 52.
+    ///
+    /// This goes through the terminal, so it also works over SSH,
+    /// but some terminals ignore it or need to be configured to allow it.
+    pub fn set_selection(&mut self, selection: Selection, text: &str) -> Result<()> {
+        let sequence = clipboard::set_sequence(selection, text, self.capabilities.multiplexer);
+        self.backend.write_all(sequence.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the text in the system clipboard. See [`Terminal::get_selection`].
+    pub fn get_clipboard(&mut self) -> Result<String> {
+        self.get_selection(Selection::Clipboard)
+    }
+
+    /// Asks the terminal for the text in `selection` with OSC 52.
+    ///
+    /// Many terminals don't allow this by default because any program could read the clipboard.
+    /// In that case, this fails with [`Error::Timeout`].
+    pub fn get_selection(&mut self, selection: Selection) -> Result<String> {
+        let sequence = clipboard::query_sequence(selection, self.capabilities.multiplexer);
+        self.backend.write_all(sequence.as_bytes())?;
+        self.read_osc_reply(clipboard::parse_reply)
+    }
+
+    /// Sets the color of underlines.
+    ///
+    /// Nothing happens if the terminal does not support [styled underlines](Capabilities::styled_underlines).
+    pub fn set_underline_color(&mut self, color: Color) -> Result<()> {
+        if !self.capabilities.styled_underlines {
+            return Ok(());
+        }
+        let color = match self.capabilities.color_depth.convert(color) {
+            Some(color) if self.state.underline_color != Some(Some(color)) => color,
+            _ => return Ok(()),
+        };
+        self.backend.set_underline_color(color)?;
+        self.state.underline_color = Some(Some(color));
+        Ok(())
+    }
+    /// Makes underlines have the color of the text again.
+    pub fn reset_underline_color(&mut self) -> Result<()> {
+        if !self.capabilities.styled_underlines || self.state.underline_color == Some(None) {
+            return Ok(());
+        }
+        self.backend.reset_underline_color()?;
+        self.state.underline_color = Some(None);
+        Ok(())
+    }
+
+    /// Sets the colors and attributes of the text that follows to `style`.
+    pub fn set_style(&mut self, style: Style) -> Result<()> {
+        match style.foreground {
+            Some(color) => self.set_foreground_color(color)?,
+            None => self.set_default_foreground_color()?,
+        }
+        match style.background {
+            Some(color) => self.set_background_color(color)?,
+            None => self.set_default_background_color()?,
+        }
+        match style.underline_color {
+            Some(color) => self.set_underline_color(color)?,
+            None => self.reset_underline_color()?,
+        }
+        self.set_attributes(style.attributes)
+    }
+
+    /// Underlines the text that follows with `underline_style` or removes the underline if it is `None`.
+    pub fn set_underline_style(&mut self, underline_style: Option<UnderlineStyle>) -> Result<()> {
+        match underline_style {
+            Some(underline_style) => self.enable_attributes(underline_style.attribute()),
+            None => self.disable_attributes(Attributes::UNDERLINES),
+        }
+    }
+
+    pub fn enable_italic(&mut self) -> Result<()> {
+        self.enable_attributes(Attributes::ITALIC)
+    }
+    pub fn disable_italic(&mut self) -> Result<()> {
+        self.disable_attributes(Attributes::ITALIC)
+    }
+
+    /// Enables `attributes` while leaving the others as they are.
+    pub fn enable_attributes(&mut self, attributes: Attributes) -> Result<()> {
+        self.change_attributes(attributes, Attributes::empty())
+    }
+    /// Disables `attributes` while leaving the others as they are.
+    pub fn disable_attributes(&mut self, attributes: Attributes) -> Result<()> {
+        self.change_attributes(Attributes::empty(), attributes)
+    }
+    /// Enables exactly `attributes` and disables all others.
+    pub fn set_attributes(&mut self, attributes: Attributes) -> Result<()> {
+        self.change_attributes(attributes, Attributes::all() - attributes)
+    }
+
+    fn change_attributes(&mut self, mut enable: Attributes, mut disable: Attributes) -> Result<()> {
+        if !self.capabilities.styled_underlines && enable.intersects(Attributes::UNDERLINES) {
+            // Fall back to a plain underline. Other terminals might mistake the parameters for other attributes.
+            enable = (enable - Attributes::UNDERLINES) | Attributes::UNDERLINE;
+            // The plain underline might be among the attributes to disable, such as with `set_attributes`
+            disable -= enable;
+        }
+
+        let known_enabled = self.state.known_attributes & self.state.attributes;
+        let known_disabled = self.state.known_attributes - self.state.attributes;
+
+        let mut to_enable = enable - known_enabled;
+        let mut to_disable = disable - known_disabled;
+        if to_enable.intersects(Attributes::UNDERLINES) {
+            // Enabling a kind of underline replaces the current one
+            to_disable -= Attributes::UNDERLINES;
+        }
+
+        let mut disabled = to_disable;
+        for group in Attributes::GROUPS {
+            if to_disable.intersects(group) {
+                // The whole group is disabled so the attributes that should stay have to be enabled again
+                disabled |= group;
+                to_enable |= (group - disable) & (known_enabled | enable);
+            }
+        }
+        if to_enable.intersects(Attributes::UNDERLINES) {
+            disabled |= Attributes::UNDERLINES;
+        }
+
+        if !to_disable.is_empty() {
+            self.backend.disable_attributes(to_disable)?;
+        }
+        if !to_enable.is_empty() {
+            self.backend.enable_attributes(to_enable)?;
+        }
+
+        self.state.attributes = (self.state.attributes - disabled) | to_enable;
+        self.state.known_attributes |= disabled | to_enable;
+        Ok(())
+    }
+
+    /// Resets the colors and attributes to the terminal's defaults.
+    pub fn reset_colors(&mut self) -> Result<()> {
+        self.backend.reset_colors()?;
+        self.state.foreground = Some(None);
+        self.state.background = Some(None);
+        self.state.underline_color = Some(None);
+        self.state.attributes = Attributes::empty();
+        self.state.known_attributes = Attributes::all();
+        Ok(())
+    }
+
+    /// Clears the screen, or only the viewport and what is below it if the terminal is inline.
+    pub fn clear(&mut self) -> Result<()> {
+        if self.viewport.is_some() {
+            self.set_screen_cursor(self.viewport_origin())?;
+            self.clear_from_cursor_to_end()?;
+        } else {
+            self.backend.clear()?;
+            // On Windows, the cursor is moved to the top left corner
+            self.state.cursor = None;
+        }
+        // Everything is empty now
+        self.previous_buffer = Buffer::new(self.buffer.size());
+        Ok(())
+    }
+    pub fn clear_from_cursor_to_end(&mut self) -> Result<()> {
+        self.backend.clear_from_cursor_to_end()
+    }
+}
+
+/// Restores the terminal if it is still initialized, for example because of an early return.
+impl<B: Backend> Drop for Terminal<B> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            // What is left to draw is no longer relevant and would end up after the panic message
+            self.backend.discard_output();
+            // The panic hook already restored the terminal
+            #[cfg(any(feature = "crossterm", feature = "termion"))]
+            if self
+                .panic_registration
+                .as_ref()
+                .is_some_and(panic::Registration::is_restored)
+            {
+                return;
+            }
+        }
+
+        if self.initialized {
+            // Errors are ignored here because there is nothing left to report them to
+            let _ = self.deinitialize();
+            let _ = self.flush();
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::{
+        backend::test::TestBackend,
+        buffer::Hyperlink,
+        event::{Key, KeyEvent, Modifiers},
+    };
+    use std::io::Write;
+
+    fn terminal() -> Terminal<TestBackend> {
+        Terminal::with_backend(TestBackend::new(Size {
+            width: 80,
+            height: 24,
+        }))
+        .unwrap()
+    }
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn test_initialize_options() {
+        let mut terminal = terminal();
+        let options = TerminalOptions::new()
+            .alternate_screen(false)
+            .raw_mode(false)
+            .title("title")
+            .mouse_capture(MouseCapture::Clicks)
+            .focus_reporting(true)
+            .panic_hook(false);
+        terminal.initialize(&options).unwrap();
+        terminal.deinitialize().unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            concat!(
+                "\x1b[?25l\x1b[22;0t\x1b]0;title\x07\x1b[?1000h\x1b[?1015h\x1b[?1006h\x1b[?1004h",
+                "\x1b[?1004l\x1b[?1006l\x1b[?1015l\x1b[?1003l\x1b[?1002l\x1b[?1000l\x1b[23;0t\x1b[?25h",
+            )
+        );
+    }
+
+    #[test]
+    fn test_deinitialize_undoes_later_changes() {
+        let mut terminal = terminal();
+        terminal
+            .initialize(&TerminalOptions::new().panic_hook(false))
+            .unwrap();
+        terminal.show_cursor().unwrap();
+        terminal.enable_bracketed_paste().unwrap();
+        terminal.set_cursor_shape(CursorShape::SteadyBar).unwrap();
+        terminal.backend_mut().flush().unwrap();
+        assert!(terminal.backend().take_output().ends_with("\x1b[6 q"));
+
+        terminal.deinitialize().unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            "\x1b[?2004l\x1b[0 q</raw>\x1b[?1049l"
+        );
+    }
+
+    #[test]
+    fn test_restore_on_drop() {
+        let mut terminal = terminal();
+        let output = terminal.backend().output.clone();
+        terminal
+            .initialize(&TerminalOptions::new().raw_mode(false).panic_hook(false))
+            .unwrap();
+        terminal.backend_mut().flush().unwrap();
+        output.take();
+
+        drop(terminal);
+        assert_eq!(output.take(), b"\x1b[?25h\x1b[?1049l");
+    }
+
+    #[test]
+    fn test_discard_output_on_panic() {
+        let mut terminal = terminal();
+        let output = terminal.backend().output.clone();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            terminal
+                .initialize(&TerminalOptions::new().raw_mode(false).panic_hook(false))
+                .unwrap();
+            terminal.write("half of a frame").unwrap();
+            panic!("while drawing");
+        }));
+        assert!(result.is_err());
+
+        // Without the panic hook, the terminal restores itself
+        assert_eq!(output.take(), b"\x1b[?25h\x1b[?1049l");
+    }
+
+    #[test]
+    fn test_present() {
+        let mut terminal = terminal();
+        terminal.present().unwrap();
+        assert_eq!(terminal.backend().take_output(), "\x1b[0m\x1b[2J");
+
+        let buffer = terminal.buffer_mut();
+        let red = Style::new().foreground(Color::Red);
+        buffer.write(Point { x: 2, y: 1 }, "ab", red);
+        buffer.write(Point { x: 6, y: 1 }, "c", red);
+        buffer.write(Point { x: 0, y: 2 }, "d", Style::default());
+        terminal.present().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            concat!("\x1b[2;3H<fg Red>ab\x1b[2Cc", "\x1b[E\x1b[39md\x1b[0m",)
+        );
+
+        // Nothing changed
+        terminal.present().unwrap();
+        assert_eq!(terminal.backend().take_output(), "");
+
+        terminal.buffer_mut().write(Point { x: 3, y: 1 }, "x", red);
+        terminal.present().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            "\x1b[2;4H<fg Red>x\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_redundant_commands() {
+        let mut terminal = terminal();
+        terminal.set_foreground_color(Color::Red).unwrap();
+        terminal.set_foreground_color(Color::Red).unwrap();
+        terminal.enable_italic().unwrap();
+        terminal.enable_italic().unwrap();
+        terminal.reset_colors().unwrap();
+        terminal.set_foreground_color(Color::Red).unwrap();
+        terminal.disable_italic().unwrap();
+
+        terminal.set_cursor(Point { x: 0, y: 0 }).unwrap();
+        terminal.reset_cursor().unwrap();
+        terminal.move_cursor_up().unwrap();
+        terminal.move_cursor_left_by(3).unwrap();
+        terminal.write("abc").unwrap();
+        terminal.set_cursor_x(3).unwrap();
+        terminal.move_cursor_down_by(0).unwrap();
+        terminal.clear().unwrap();
+        terminal.set_cursor(Point { x: 3, y: 0 }).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            "<fg Red>\x1b[3m\x1b[0m<fg Red>\x1b[1;1Habc\x1b[2J\x1b[1;4H"
+        );
+    }
+
+    #[test]
+    fn test_set_style() {
+        let mut terminal = terminal();
+        terminal.set_capabilities(Capabilities {
+            styled_underlines: true,
+            ..Capabilities::default()
+        });
+        terminal.reset_colors().unwrap();
+        let style = Style::new()
+            .underline_color(Color::Red)
+            .attributes(Attributes::BOLD | Attributes::DIM | Attributes::UNDERLINE);
+        terminal.set_style(style).unwrap();
+        terminal
+            .set_style(Style {
+                attributes: Attributes::DIM | Attributes::CURLY_UNDERLINE,
+                ..style
+            })
+            .unwrap();
+        terminal.set_style(Style::default()).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            concat!(
+                "\x1b[0m\x1b[58;5;9m\x1b[1;2;4m",
+                "\x1b[22m\x1b[2;4:3m",
+                "\x1b[59m\x1b[22;24m",
+            )
+        );
+    }
+
+    #[test]
+    fn test_underline_fallback() {
+        let mut terminal = terminal();
+        terminal.reset_colors().unwrap();
+        let style = Style::new()
+            .underline(UnderlineStyle::Curly)
+            .underline_color(Color::Rgb { r: 255, g: 0, b: 0 });
+        terminal.set_style(style).unwrap();
+        terminal.set_underline_style(None).unwrap();
+        terminal.set_capabilities(Capabilities {
+            styled_underlines: true,
+            ..Capabilities::default()
+        });
+        terminal.set_style(style).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            concat!("\x1b[0m\x1b[4m\x1b[24m", "\x1b[58;2;255;0;0m\x1b[4:3m",)
+        );
+    }
+
+    #[test]
+    fn test_underline_fallback_stays_enabled() {
+        let mut terminal = terminal();
+        let style = Style::new().underline(UnderlineStyle::Curly);
+        terminal.reset_colors().unwrap();
+        terminal.set_style(style).unwrap();
+        terminal.set_style(style).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(terminal.backend().take_output(), "\x1b[0m\x1b[4m");
+
+        terminal
+            .buffer_mut()
+            .write(Point { x: 0, y: 0 }, "abcd", style);
+        terminal.present().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            "\x1b[0m\x1b[2J\x1b[1;1H\x1b[4mabcd\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_color_depth() {
+        let mut terminal = terminal();
+        let orange = Color::Rgb {
+            r: 255,
+            g: 135,
+            b: 0,
+        };
+        terminal.set_foreground_color(orange).unwrap();
+        terminal.set_color_depth(ColorDepth::EightBit);
+        terminal.set_foreground_color(orange).unwrap();
+        terminal.set_color_depth(ColorDepth::FourBit);
+        terminal.set_foreground_color(orange).unwrap();
+        terminal.set_background_color(Color::Byte(21)).unwrap();
+        terminal.set_color_depth(ColorDepth::Monochrome);
+        terminal.set_foreground_color(Color::Green).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            "<fg Rgb { r: 255, g: 135, b: 0 }><fg Byte(208)><fg DarkYellow><bg DarkBlue>"
+        );
+        assert_eq!(terminal.color_depth(), ColorDepth::Monochrome);
+    }
+
+    #[test]
+    fn test_unknown_attributes() {
+        let mut terminal = terminal();
+        terminal.enable_italic().unwrap();
+        terminal.enable_italic().unwrap();
+        terminal.disable_attributes(Attributes::BOLD).unwrap();
+        terminal.enable_attributes(Attributes::DIM).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(terminal.backend().take_output(), "\x1b[3m\x1b[22m\x1b[2m");
+    }
+
+    #[test]
+    fn test_deinitialize_uninitialized() {
+        let mut terminal = terminal();
+        terminal.deinitialize().unwrap();
+        terminal.flush().unwrap();
+        assert!(terminal.backend().take_output().is_empty());
+    }
+
+    #[test]
+    fn test_poll_event() {
+        let mut terminal = terminal();
+        terminal.backend_mut().input.push_back(b"a".to_vec());
+
+        assert_eq!(
+            terminal.poll_event(Duration::ZERO).unwrap(),
+            Some(Event::Key(KeyEvent::new(
+                Key::Char('a'),
+                Modifiers::empty()
+            )))
+        );
+        assert_eq!(terminal.poll_event(Duration::ZERO).unwrap(), None);
+    }
+
+    #[test]
+    fn test_query_colors() {
+        let mut terminal = terminal();
+        let input = &mut terminal.backend_mut().input;
+        input.push_back(b"a\x1b]11;rgb:0000/0000/0000\x07\x1b]4;3;rgb:cd".to_vec());
+        input.push_back(b"cd/cdcd/0000\x1b\\".to_vec());
+
+        assert_eq!(
+            terminal.query_palette_color(3).unwrap(),
+            Color::Rgb {
+                r: 205,
+                g: 205,
+                b: 0
+            }
+        );
+        assert_eq!(terminal.backend().take_output(), "\x1b]4;3;?\x07");
+        assert_eq!(
+            terminal.read_event().unwrap(),
+            Event::Key(KeyEvent::new(Key::Char('a'), Modifiers::empty()))
+        );
+
+        terminal.set_reply_timeout(Duration::from_millis(10));
+        assert!(matches!(
+            terminal.query_background_color(),
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_change_colors() {
+        let mut terminal = terminal();
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"\x1b]4;21;rgb:0000/0000/ffff\x07".to_vec());
+        terminal.set_reply_timeout(Duration::from_millis(10));
+
+        terminal
+            .change_foreground_color(Color::Rgb {
+                r: 255,
+                g: 128,
+                b: 0,
+            })
+            .unwrap();
+        terminal.change_background_color(Color::Byte(21)).unwrap();
+        terminal.change_cursor_color(Color::Red).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            concat!(
+                "\x1b]10;#ff8000\x07",
+                "\x1b]4;21;?\x07\x1b]11;#0000ff\x07",
+                "\x1b]4;9;?\x07\x1b]12;#ff0000\x07",
+            )
+        );
+    }
+
+    #[test]
+    fn test_clipboard() {
+        let mut terminal = terminal();
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"\x1b]52;p;8J+YgA==\x07".to_vec());
+
+        terminal.set_clipboard("hi").unwrap();
+        assert_eq!(terminal.get_selection(Selection::Primary).unwrap(), "😀");
+        terminal.set_capabilities(Capabilities {
+            multiplexer: Some(Multiplexer::Tmux),
+            ..Capabilities::default()
+        });
+        terminal.set_selection(Selection::Primary, "").unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            concat!(
+                "\x1b]52;c;aGk=\x07\x1b]52;p;?\x07",
+                "\x1bPtmux;\x1b\x1b]52;p;\x07\x1b\\",
+            )
+        );
+    }
+
+    #[test]
+    fn test_hyperlinks() {
+        let mut terminal = terminal();
+        terminal
+            .start_hyperlink("https://example.com/a b\x1b", Some("x;1\x07"))
+            .unwrap();
+        terminal.end_hyperlink().unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            "\x1b]8;id=x1;https://example.com/a%20b%1B\x07\x1b]8;;\x07"
+        );
+
+        terminal.present().unwrap();
+        terminal.backend().take_output();
+        let buffer = terminal.buffer_mut();
+        let link = buffer.add_hyperlink(Hyperlink::new("https://example.com"));
+        buffer.write_link(Point { x: 0, y: 0 }, "ab", Style::default(), link);
+        buffer.write(Point { x: 2, y: 0 }, "c", Style::default());
+        buffer.write_link(Point { x: 5, y: 0 }, "d", Style::default(), link);
+        terminal.present().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            concat!(
+                "\x1b[1;1H\x1b]8;;https://example.com\x07ab",
+                "\x1b]8;;\x07c\x1b[2C\x1b]8;;https://example.com\x07d",
+                "\x1b]8;;\x07\x1b[0m",
+            )
+        );
+    }
+
+    #[test]
+    fn test_cursor_position() {
+        let mut terminal = terminal();
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"b\x1b[3;7R".to_vec());
+
+        assert_eq!(terminal.cursor_position().unwrap(), Point { x: 6, y: 2 });
+        assert_eq!(
+            terminal.read_event().unwrap(),
+            Event::Key(KeyEvent::new(Key::Char('b'), Modifiers::empty()))
+        );
+        terminal.set_cursor(Point { x: 6, y: 2 }).unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(terminal.backend().take_output(), "\x1b[6n");
+
+        terminal.set_reply_timeout(Duration::from_millis(50));
+        assert!(matches!(terminal.cursor_position(), Err(Error::Timeout)));
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"\x1b[24;5Rc".to_vec());
+        assert_eq!(
+            terminal.read_event().unwrap(),
+            Event::Key(KeyEvent::new(Key::Char('c'), Modifiers::empty()))
+        );
+
+        // Once the reply is too late, the same input is a key again
+        assert!(matches!(terminal.cursor_position(), Err(Error::Timeout)));
+        std::thread::sleep(Duration::from_millis(100));
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"\x1b[1;2R".to_vec());
+        assert_eq!(
+            terminal.read_event().unwrap(),
+            Event::Key(KeyEvent::new(Key::F(3), Modifiers::SHIFT))
+        );
+    }
+
+    #[test]
+    fn test_inline() {
+        let mut terminal = terminal();
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"\x1b[23;5R".to_vec());
+        terminal
+            .initialize(
+                &TerminalOptions::new()
+                    .hide_cursor(false)
+                    .panic_hook(false)
+                    .inline(3),
+            )
+            .unwrap();
+        assert_eq!(terminal.viewport_origin(), Point { x: 0, y: 21 });
+        assert_eq!(
+            terminal.buffer().size(),
+            Size {
+                width: 80,
+                height: 3
+            }
+        );
+
+        terminal
+            .buffer_mut()
+            .write(Point { x: 0, y: 1 }, "hi", Style::default());
+        terminal.present().unwrap();
+        terminal.deinitialize().unwrap();
+        terminal.backend_mut().flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            concat!(
+                "<raw>\x1b[6n\n\n\n",
+                "\x1b[0m\x1b[22;1H\x1b[J\x1b[Ehi\x1b[0m",
+                "\x1b[0m\x1b[24;1H\r\n</raw>",
+            )
+        );
+        assert_eq!(terminal.buffer().size(), terminal.size);
+    }
+
+    #[test]
+    fn test_inline_cursor() {
+        let mut terminal = terminal();
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"\x1b[23;5R".to_vec());
+        terminal
+            .initialize(
+                &TerminalOptions::new()
+                    .hide_cursor(false)
+                    .panic_hook(false)
+                    .inline(3),
+            )
+            .unwrap();
+        terminal.flush().unwrap();
+        terminal.backend().take_output();
+
+        terminal.set_cursor(Point { x: 1, y: 1 }).unwrap();
+        terminal.set_cursor_y(2).unwrap();
+        terminal.reset_cursor().unwrap();
+        terminal.clear().unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            "\x1b[23;2H\x1b[24d\x1b[22;1H\x1b[J"
+        );
+
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"\x1b[23;2R".to_vec());
+        assert_eq!(terminal.cursor_position().unwrap(), Point { x: 1, y: 1 });
+        assert!(terminal.contains(Point { x: 1, y: 2 }));
+        assert!(!terminal.contains(Point { x: 1, y: 3 }));
+    }
+
+    #[test]
+    fn test_inline_bogus_cursor_position() {
+        let options = TerminalOptions::new()
+            .hide_cursor(false)
+            .panic_hook(false)
+            .inline(u16::MAX);
+        assert_eq!(options.clone().alternate_screen(true).inline, None);
+        assert!(
+            !options
+                .clone()
+                .alternate_screen(true)
+                .inline(3)
+                .alternate_screen
+        );
+
+        let mut terminal = terminal();
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"\x1b[65535;65535R".to_vec());
+        terminal.initialize(&options).unwrap();
+        assert_eq!(terminal.viewport_origin(), Point { x: 0, y: 0 });
+        assert_eq!(terminal.buffer().size(), terminal.size);
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            format!("<raw>\x1b[6n{}", "\n".repeat(24))
+        );
+    }
+
+    #[test]
+    fn test_inline_resize() {
+        let mut terminal = terminal();
+        terminal
+            .backend_mut()
+            .input
+            .push_back(b"\x1b[6;1R".to_vec());
+        let options = TerminalOptions::new()
+            .raw_mode(false)
+            .hide_cursor(false)
+            .panic_hook(false)
+            .inline(4)
+            .inline_cleanup(InlineCleanup::Clear);
+        assert!(matches!(
+            terminal.initialize(&options),
+            Err(Error::InvalidOptions(_))
+        ));
+        terminal.initialize(&options.raw_mode(true)).unwrap();
+        assert_eq!(terminal.viewport_origin(), Point { x: 0, y: 5 });
+
+        terminal.backend_mut().size = Size {
+            width: 40,
+            height: 7,
+        };
+        assert_eq!(terminal.read_event().unwrap(), Event::Resize);
+        assert_eq!(terminal.viewport_origin(), Point { x: 0, y: 3 });
+        assert_eq!(
+            terminal.buffer().size(),
+            Size {
+                width: 40,
+                height: 4
+            }
+        );
+
+        terminal.flush().unwrap();
+        terminal.backend().take_output();
+        terminal.deinitialize().unwrap();
+        terminal.flush().unwrap();
+        assert_eq!(
+            terminal.backend().take_output(),
+            "\x1b[0m\x1b[4;1H\x1b[J</raw>"
+        );
+    }
 }
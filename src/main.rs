@@ -1,14 +1,44 @@
 //! This file exists for testing purposes.
 
 use std::io;
+use tanmatsu::{
+    event::{Event, Key, KeyEvent},
+    util::Point,
+    MouseCapture, TerminalOptions,
+};
 
-fn main() {
+fn main() -> tanmatsu::Result<()> {
     let stdout = io::stdout();
-    let mut terminal = tanmatsu::Terminal::new(stdout.lock()).unwrap();
+    let mut terminal = tanmatsu::Terminal::new(stdout.lock())?;
 
-    terminal.initialize(None, false);
+    terminal.initialize(
+        &TerminalOptions::new()
+            .mouse_capture(MouseCapture::All)
+            .bracketed_paste(true)
+            .focus_reporting(true),
+    )?;
 
-    terminal.flush();
+    terminal.flush()?;
 
-    std::thread::park();
+    let mut y = 1;
+    loop {
+        let event = terminal.read_event()?;
+        if let Event::Key(KeyEvent {
+            key: Key::Char('q'),
+            ..
+        }) = event
+        {
+            break;
+        }
+
+        terminal.set_cursor(Point { x: 0, y })?;
+        terminal.write(&format!("{:?}", event))?;
+        terminal.flush()?;
+        y = y % terminal.size.height.saturating_sub(1).max(1) + 1;
+    }
+
+    terminal.deinitialize()?;
+    terminal.flush()?;
+
+    Ok(())
 }
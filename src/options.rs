@@ -0,0 +1,195 @@
+//! Options that control how a terminal is initialized.
+
+use bitflags::bitflags;
+
+/// Which mouse events are reported when mouse capture is enabled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MouseCapture {
+    /// Presses, releases and scrolling.
+    Clicks,
+    /// Like [`MouseCapture::Clicks`] but also movement while a button is held down.
+    Drag,
+    /// Like [`MouseCapture::Drag`] but also movement while no button is held down.
+    All,
+}
+
+/// The shapes of the cursor, which can either blink or be steady.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CursorShape {
+    /// The shape the user configured.
+    Default,
+    BlinkingBlock,
+    SteadyBlock,
+    BlinkingUnderline,
+    SteadyUnderline,
+    /// A vertical line, also known as a beam.
+    BlinkingBar,
+    SteadyBar,
+}
+
+impl CursorShape {
+    /// Returns the parameter of the DECSCUSR sequence that sets this shape.
+    pub(crate) fn parameter(self) -> u8 {
+        match self {
+            CursorShape::Default => 0,
+            CursorShape::BlinkingBlock => 1,
+            CursorShape::SteadyBlock => 2,
+            CursorShape::BlinkingUnderline => 3,
+            CursorShape::SteadyUnderline => 4,
+            CursorShape::BlinkingBar => 5,
+            CursorShape::SteadyBar => 6,
+        }
+    }
+}
+
+/// What happens to an inline viewport on deinitialization. See [`TerminalOptions::inline`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InlineCleanup {
+    /// The content stays and the cursor is moved to the line below it, so that it ends up in the scrollback.
+    Keep,
+    /// The content is cleared and the cursor is moved to where the viewport started.
+    Clear,
+}
+
+/// Describes what [`Terminal::initialize`](crate::Terminal::initialize) should do.
+///
+/// By default, the alternate screen is entered, raw mode is enabled, the cursor is hidden
+/// and a panic hook that restores the terminal is installed.
+///
+/// # Examples
+///
+/// ```
+/// use tanmatsu::{MouseCapture, TerminalOptions};
+///
+/// let options = TerminalOptions::new()
+///     .title("Editor")
+///     .mouse_capture(MouseCapture::Drag)
+///     .bracketed_paste(true);
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TerminalOptions {
+    pub(crate) alternate_screen: bool,
+    pub(crate) raw_mode: bool,
+    pub(crate) hide_cursor: bool,
+    pub(crate) mouse_capture: Option<MouseCapture>,
+    pub(crate) bracketed_paste: bool,
+    pub(crate) focus_reporting: bool,
+    pub(crate) title: Option<String>,
+    pub(crate) panic_hook: bool,
+    pub(crate) inline: Option<u16>,
+    pub(crate) inline_cleanup: InlineCleanup,
+}
+
+impl Default for TerminalOptions {
+    fn default() -> Self {
+        Self {
+            alternate_screen: true,
+            raw_mode: true,
+            hide_cursor: true,
+            mouse_capture: None,
+            bracketed_paste: false,
+            focus_reporting: false,
+            title: None,
+            panic_hook: true,
+            inline: None,
+            inline_cleanup: InlineCleanup::Keep,
+        }
+    }
+}
+
+impl TerminalOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to draw in the alternate screen, which leaves the previous content of the terminal untouched.
+    ///
+    /// Enabling it draws to the whole screen again if [`TerminalOptions::inline`] was called before.
+    pub fn alternate_screen(mut self, alternate_screen: bool) -> Self {
+        self.alternate_screen = alternate_screen;
+        if alternate_screen {
+            self.inline = None;
+        }
+        self
+    }
+
+    /// Draws into `height` lines at the cursor rather than the whole screen, which also disables the alternate screen.
+    ///
+    /// The lines start at the line of the cursor, or the next one if there is text before the cursor,
+    /// and the content above is scrolled up if there are not enough lines below.
+    /// The buffer has the size of these lines and is drawn into them.
+    /// The cursor methods of [`Terminal`](crate::Terminal) and [`Terminal::clear`](crate::Terminal::clear)
+    /// work within these lines too.
+    ///
+    /// This queries the cursor position, so [`Terminal::initialize`](crate::Terminal::initialize) fails with
+    /// [`Error::InvalidOptions`](crate::Error::InvalidOptions) if raw mode is not enabled.
+    pub fn inline(mut self, height: u16) -> Self {
+        self.inline = Some(height);
+        self.alternate_screen = false;
+        self
+    }
+
+    /// What happens to the inline viewport on deinitialization. By default, its content is kept.
+    pub fn inline_cleanup(mut self, inline_cleanup: InlineCleanup) -> Self {
+        self.inline_cleanup = inline_cleanup;
+        self
+    }
+
+    /// Whether input should be passed through directly rather than line by line and without being echoed.
+    pub fn raw_mode(mut self, raw_mode: bool) -> Self {
+        self.raw_mode = raw_mode;
+        self
+    }
+
+    pub fn hide_cursor(mut self, hide_cursor: bool) -> Self {
+        self.hide_cursor = hide_cursor;
+        self
+    }
+
+    /// Which mouse events to report, if any.
+    pub fn mouse_capture(mut self, mouse_capture: impl Into<Option<MouseCapture>>) -> Self {
+        self.mouse_capture = mouse_capture.into();
+        self
+    }
+
+    /// Whether pasted text should arrive as [`Event::Paste`](crate::event::Event::Paste).
+    pub fn bracketed_paste(mut self, bracketed_paste: bool) -> Self {
+        self.bracketed_paste = bracketed_paste;
+        self
+    }
+
+    /// Whether [`Event::FocusGained`](crate::event::Event::FocusGained) and
+    /// [`Event::FocusLost`](crate::event::Event::FocusLost) should be reported.
+    pub fn focus_reporting(mut self, focus_reporting: bool) -> Self {
+        self.focus_reporting = focus_reporting;
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Whether to install a panic hook that restores the terminal before the panic message is printed.
+    pub fn panic_hook(mut self, panic_hook: bool) -> Self {
+        self.panic_hook = panic_hook;
+        self
+    }
+}
+
+bitflags! {
+    /// The terminal modes that are currently in effect and have to be undone on deinitialization.
+    #[derive(Default)]
+    pub(crate) struct Modes: u8 {
+        const ALTERNATE_SCREEN = 1 << 0;
+        const RAW_MODE = 1 << 1;
+        const HIDDEN_CURSOR = 1 << 2;
+        const MOUSE_CAPTURE = 1 << 3;
+        const BRACKETED_PASTE = 1 << 4;
+        const FOCUS_REPORTING = 1 << 5;
+        /// The previous title was saved on the title stack.
+        const SAVED_TITLE = 1 << 6;
+        /// The cursor shape is not the default.
+        const CURSOR_SHAPE = 1 << 7;
+    }
+}
@@ -0,0 +1,204 @@
+//! Restoring the terminal when the program panics.
+
+use crate::{
+    options::{InlineCleanup, Modes},
+    Viewport,
+};
+use std::{
+    panic,
+    sync::{
+        atomic::{AtomicU64, AtomicU8, Ordering},
+        Once,
+    },
+};
+
+static INSTALL: Once = Once::new();
+
+/// The ID of the [`Registration`] whose terminal the hook restores, or 0 if there is none.
+static OWNER: AtomicU64 = AtomicU64::new(0);
+/// The ID of the [`Registration`] whose terminal the hook restored.
+static RESTORED: AtomicU64 = AtomicU64::new(0);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+/// The modes of the registered terminal that are currently in effect.
+/// The hook uses this because modes might change after it was installed.
+static MODES: AtomicU8 = AtomicU8::new(0);
+/// The inline viewport of the registered terminal as encoded by [`encode_viewport`], or 0 if there is none.
+static VIEWPORT: AtomicU64 = AtomicU64::new(0);
+
+/// Makes the panic hook restore the terminal that holds this, which publishes its state through it.
+///
+/// Only the terminal that registered last is restored, and nothing is restored for it anymore once this is dropped.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    id: u64,
+}
+
+impl Registration {
+    pub(crate) fn new() -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        MODES.store(0, Ordering::Relaxed);
+        VIEWPORT.store(0, Ordering::Relaxed);
+        OWNER.store(id, Ordering::Relaxed);
+        Self { id }
+    }
+
+    fn is_owner(&self) -> bool {
+        OWNER.load(Ordering::Relaxed) == self.id
+    }
+
+    pub(crate) fn set_modes(&self, modes: Modes) {
+        if self.is_owner() {
+            MODES.store(modes.bits(), Ordering::Relaxed);
+        }
+    }
+
+    /// Publishes the inline viewport, whose height must fit into the terminal.
+    pub(crate) fn set_viewport(&self, viewport: Option<Viewport>) {
+        if self.is_owner() {
+            VIEWPORT.store(viewport.map_or(0, encode_viewport), Ordering::Relaxed);
+        }
+    }
+
+    /// Whether the hook already restored the terminal.
+    pub(crate) fn is_restored(&self) -> bool {
+        RESTORED.load(Ordering::Relaxed) == self.id
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if OWNER
+            .compare_exchange(self.id, 0, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            MODES.store(0, Ordering::Relaxed);
+            VIEWPORT.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Installs a panic hook that restores the modes of the terminal that are in effect at the time of the panic,
+/// before the previous hook prints the panic message.
+///
+/// The hook is installed only once no matter how often this is called.
+pub(crate) fn install_hook() {
+    INSTALL.call_once(|| {
+        #[cfg(any(unix, windows))]
+        crate::sys::save_mode();
+
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |panic_info| {
+            let owner = OWNER.swap(0, Ordering::Relaxed);
+            if owner != 0 {
+                RESTORED.store(owner, Ordering::Relaxed);
+                restore(
+                    Modes::from_bits_truncate(MODES.swap(0, Ordering::Relaxed)),
+                    decode_viewport(VIEWPORT.swap(0, Ordering::Relaxed)),
+                );
+            }
+            previous_hook(panic_info);
+        }));
+    });
+}
+
+/// Packs `viewport` into a number that is never 0.
+fn encode_viewport(viewport: Viewport) -> u64 {
+    let clear = (viewport.cleanup == InlineCleanup::Clear) as u64;
+    1 << 33 | clear << 32 | (viewport.y as u64) << 16 | viewport.height as u64
+}
+
+fn decode_viewport(encoded: u64) -> Option<Viewport> {
+    if encoded == 0 {
+        return None;
+    }
+    Some(Viewport {
+        y: (encoded >> 16) as u16,
+        height: encoded as u16,
+        cleanup: if encoded >> 32 & 1 == 1 {
+            InlineCleanup::Clear
+        } else {
+            InlineCleanup::Keep
+        },
+    })
+}
+
+/// Undoes `modes` and leaves `viewport` like on deinitialization without going through a backend or `io::Stdout`.
+fn restore(modes: Modes, viewport: Option<Viewport>) {
+    let mut output = Vec::new();
+    if let Some(viewport) = viewport {
+        // The panic message is printed below the viewport or where it was instead of over it
+        output.extend_from_slice(b"\x1b[0m");
+        let sequence = match viewport.cleanup {
+            InlineCleanup::Keep => {
+                format!("\x1b[{};1H\r\n", viewport.y as u32 + viewport.height as u32)
+            }
+            InlineCleanup::Clear => format!("\x1b[{};1H\x1b[J", viewport.y as u32 + 1),
+        };
+        output.extend_from_slice(sequence.as_bytes());
+    }
+    if modes.contains(Modes::FOCUS_REPORTING) {
+        output.extend_from_slice(b"\x1b[?1004l");
+    }
+    if modes.contains(Modes::BRACKETED_PASTE) {
+        output.extend_from_slice(b"\x1b[?2004l");
+    }
+    if modes.contains(Modes::MOUSE_CAPTURE) {
+        output.extend_from_slice(b"\x1b[?1006l\x1b[?1015l\x1b[?1003l\x1b[?1002l\x1b[?1000l");
+    }
+    if modes.contains(Modes::SAVED_TITLE) {
+        output.extend_from_slice(b"\x1b[23;0t");
+    }
+    if modes.contains(Modes::CURSOR_SHAPE) {
+        output.extend_from_slice(b"\x1b[0 q");
+    }
+    if modes.contains(Modes::HIDDEN_CURSOR) {
+        output.extend_from_slice(b"\x1b[?25h");
+    }
+    if modes.contains(Modes::ALTERNATE_SCREEN) {
+        output.extend_from_slice(b"\x1b[?1049l");
+    }
+
+    #[cfg(any(unix, windows))]
+    crate::sys::restore(&output, modes.contains(Modes::RAW_MODE));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registration() {
+        let first = Registration::new();
+        first.set_modes(Modes::RAW_MODE);
+        assert_eq!(MODES.load(Ordering::Relaxed), Modes::RAW_MODE.bits());
+
+        // Only the terminal that registered last publishes its modes
+        let second = Registration::new();
+        first.set_modes(Modes::HIDDEN_CURSOR);
+        assert_eq!(MODES.load(Ordering::Relaxed), 0);
+        second.set_modes(Modes::ALTERNATE_SCREEN);
+        drop(first);
+        assert_eq!(
+            MODES.load(Ordering::Relaxed),
+            Modes::ALTERNATE_SCREEN.bits()
+        );
+
+        drop(second);
+        assert_eq!(OWNER.load(Ordering::Relaxed), 0);
+        assert_eq!(MODES.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_encode_viewport() {
+        for cleanup in [InlineCleanup::Keep, InlineCleanup::Clear] {
+            let viewport = Viewport {
+                y: u16::MAX,
+                height: 0,
+                cleanup,
+            };
+            assert_ne!(encode_viewport(viewport), 0);
+            assert_eq!(decode_viewport(encode_viewport(viewport)), Some(viewport));
+        }
+        assert_eq!(decode_viewport(0), None);
+    }
+}
@@ -0,0 +1,774 @@
+//! Decoding of the raw input a terminal sends into events.
+//!
+//! This is independent of the backend so that all backends report input the same way.
+
+use crate::{
+    event::{Event, Key, KeyEvent, Modifiers, MouseButton, MouseEvent, MouseEventKind},
+    util::Point,
+};
+use std::{collections::VecDeque, str, time::Instant};
+
+const ESC: u8 = 0x1b;
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+const OSC_START: &[u8] = b"\x1b]";
+const BEL: u8 = 0x07;
+
+/// A reply of the terminal to a query. Unlike events, it is not caused by the user.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Reply {
+    /// An operating system command without the introducer and terminator, such as `11;rgb:0000/0000/0000`.
+    Osc(String),
+    /// The position of the cursor, which is `ESC [ row ; column R`.
+    CursorPosition(Point),
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Parser {
+    /// Input that does not form a complete event yet.
+    buffer: Vec<u8>,
+    events: VecDeque<Event>,
+    replies: VecDeque<Reply>,
+    /// Whether the reply to an OSC query is awaited.
+    /// Only then is an OSC sequence decoded because its start looks the same as `]` with the Alt key held down.
+    osc_expected: bool,
+    /// The replies with the position of the cursor that were asked for but did not arrive yet, in order.
+    /// Only then is one decoded because it looks the same as the F3 key with modifiers.
+    ///
+    /// A reply that nothing waits for anymore is only expected until the time it holds.
+    cursor_positions_expected: VecDeque<Option<Instant>>,
+}
+
+impl Parser {
+    /// Decodes `bytes` into events.
+    ///
+    /// `more` tells whether more input is likely to follow right away.
+    /// It is needed to tell a press of the Escape key apart from the start of an escape sequence.
+    pub fn advance(&mut self, bytes: &[u8], more: bool) {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut start = 0;
+        loop {
+            let buffer = &self.buffer[start..];
+            // Unless a reply is expected, `ESC ]` is the key `]` with the Alt key held down
+            if self.osc_expected {
+                if buffer.starts_with(OSC_START) {
+                    match parse_osc(buffer) {
+                        Some((length, reply)) => {
+                            start += length;
+                            self.replies.push_back(reply);
+                            continue;
+                        }
+                        None => break,
+                    }
+                }
+                // The rest of the introducer might still follow
+                if buffer == [ESC] {
+                    break;
+                }
+            }
+
+            if !self.cursor_positions_expected.is_empty() {
+                if let Some((length, point)) = parse_cursor_position(buffer) {
+                    start += length;
+                    self.cursor_positions_expected.pop_front();
+                    self.replies.push_back(Reply::CursorPosition(point));
+                    continue;
+                }
+            }
+
+            match parse(buffer, more) {
+                Some((length, event)) => {
+                    start += length;
+                    if let Some(event) = event {
+                        self.events.push_back(event);
+                    }
+                }
+                None => break,
+            }
+        }
+        self.buffer.drain(..start);
+    }
+
+    pub fn next(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Adds an event that did not come from the input, to be returned after the ones already decoded.
+    pub fn push(&mut self, event: Event) {
+        self.events.push_back(event);
+    }
+
+    /// Sets whether an OSC reply is expected. See [`Reply::Osc`].
+    ///
+    /// Once it no longer is, input that was held back because it could have been the start of one is decoded as keys.
+    pub fn expect_osc(&mut self, expected: bool) {
+        self.osc_expected = expected;
+        if !expected {
+            self.advance(&[], false);
+        }
+    }
+
+    /// Makes the next reply with the position of the cursor be decoded as one. See [`Reply::CursorPosition`].
+    pub fn expect_cursor_position(&mut self) {
+        self.cursor_positions_expected.push_back(None);
+    }
+
+    /// Keeps expecting the reply that was asked for last only until `deadline`, because nothing waits for it anymore.
+    ///
+    /// A reply that arrives late is still decoded as one until then instead of as a key.
+    pub fn give_up_cursor_position(&mut self, deadline: Instant) {
+        if let Some(expected) = self.cursor_positions_expected.back_mut() {
+            *expected = Some(deadline);
+        }
+    }
+
+    /// Stops expecting the replies that were given up on and did not arrive by their deadline.
+    pub fn expire_cursor_positions(&mut self, now: Instant) {
+        while let Some(Some(deadline)) = self.cursor_positions_expected.front() {
+            if *deadline > now {
+                break;
+            }
+            self.cursor_positions_expected.pop_front();
+        }
+    }
+
+    pub fn next_reply(&mut self) -> Option<Reply> {
+        self.replies.pop_front()
+    }
+}
+
+/// Parses the event at the start of `buffer`.
+///
+/// Returns the length of the input that was consumed and the event, if it is one that is supported.
+/// Returns `None` if `buffer` does not contain a complete event yet.
+fn parse(buffer: &[u8], more: bool) -> Option<(usize, Option<Event>)> {
+    let key = |length, key, modifiers| Some((length, Some(key_event(key, modifiers))));
+
+    match *buffer.first()? {
+        ESC => match buffer.get(1) {
+            None if more => None,
+            None => key(1, Key::Esc, Modifiers::empty()),
+            Some(b'[') => parse_csi(buffer),
+            Some(b'O') => parse_ss3(buffer),
+            Some(&ESC) => key(1, Key::Esc, Modifiers::empty()),
+            // An escape byte in front of a key means that the Alt key was held down
+            Some(_) => parse(&buffer[1..], more).map(|(length, event)| {
+                let event = match event {
+                    Some(Event::Key(mut key_event)) => {
+                        key_event.modifiers |= Modifiers::ALT;
+                        Some(Event::Key(key_event))
+                    }
+                    event => event,
+                };
+                (length + 1, event)
+            }),
+        },
+        b'\r' | b'\n' => key(1, Key::Enter, Modifiers::empty()),
+        b'\t' => key(1, Key::Tab, Modifiers::empty()),
+        0x7f | 0x08 => key(1, Key::Backspace, Modifiers::empty()),
+        byte @ 0x01..=0x1a => key(1, Key::Char((byte - 0x01 + b'a') as char), Modifiers::CTRL),
+        byte @ 0x1c..=0x1f => key(1, Key::Char((byte - 0x1c + b'4') as char), Modifiers::CTRL),
+        0x00 => key(1, Key::Null, Modifiers::empty()),
+        _ => parse_char(buffer),
+    }
+}
+
+fn key_event(key: Key, modifiers: Modifiers) -> Event {
+    Event::Key(KeyEvent::new(key, modifiers))
+}
+
+/// Parses the modifier parameter that is part of some escape sequences.
+///
+/// It is one more than a bit set of the held down modifiers, which [`Modifiers`] mirrors.
+fn parse_modifiers(parameter: Option<&u32>) -> Modifiers {
+    let bits = parameter.map_or(0, |parameter| parameter.saturating_sub(1));
+    Modifiers::from_bits_truncate(bits as u8)
+}
+
+/// Parses a UTF-8 encoded character.
+fn parse_char(buffer: &[u8]) -> Option<(usize, Option<Event>)> {
+    let length = match buffer[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        // Not the start of a character
+        _ => return Some((1, None)),
+    };
+    let bytes = buffer.get(..length)?;
+
+    match str::from_utf8(bytes) {
+        Ok(string) => {
+            let char = string.chars().next()?;
+            Some((length, Some(key_event(Key::Char(char), Modifiers::empty()))))
+        }
+        Err(_) => Some((1, None)),
+    }
+}
+
+/// Parses an SS3 sequence, which is `ESC O` followed by a single byte.
+fn parse_ss3(buffer: &[u8]) -> Option<(usize, Option<Event>)> {
+    let key = match *buffer.get(2)? {
+        b'A' => Key::Up,
+        b'B' => Key::Down,
+        b'C' => Key::Right,
+        b'D' => Key::Left,
+        b'H' => Key::Home,
+        b'F' => Key::End,
+        byte @ b'P'..=b'S' => Key::F(byte - b'P' + 1),
+        _ => return Some((3, None)),
+    };
+    Some((3, Some(key_event(key, Modifiers::empty()))))
+}
+
+/// Parses a CSI sequence, which is `ESC [` followed by parameters and a final byte.
+fn parse_csi(buffer: &[u8]) -> Option<(usize, Option<Event>)> {
+    if buffer.get(2) == Some(&b'M') {
+        return parse_x10_mouse(buffer);
+    }
+    if buffer.starts_with(PASTE_START) {
+        return parse_paste(buffer);
+    }
+
+    let mut end = 2;
+    loop {
+        match *buffer.get(end)? {
+            0x20..=0x3f => end += 1,
+            0x40..=0x7e => break,
+            // A malformed sequence; drop it and continue with what follows
+            _ => return Some((end, None)),
+        }
+    }
+    let length = end + 1;
+    let parameters = &buffer[2..end];
+    let final_byte = buffer[end];
+
+    let event = match parameters.first() {
+        // Focus reporting: `ESC [ I` and `ESC [ O`
+        None if final_byte == b'I' => Some(Event::FocusGained),
+        None if final_byte == b'O' => Some(Event::FocusLost),
+        Some(b'<') => parse_sgr_mouse(&parameters[1..], final_byte),
+        Some(b'0'..=b'9') | None => {
+            let parameters = parse_parameters(parameters);
+            match final_byte {
+                b'M' => parse_rxvt_mouse(&parameters),
+                b'~' => parse_special_key(&parameters),
+                b'u' => parse_codepoint_key(&parameters),
+                _ => parse_csi_key(final_byte, &parameters),
+            }
+        }
+        _ => None,
+    };
+    Some((length, event))
+}
+
+/// Parses text pasted in bracketed paste mode, which is enclosed in `ESC [ 200 ~` and `ESC [ 201 ~`.
+fn parse_paste(buffer: &[u8]) -> Option<(usize, Option<Event>)> {
+    let text = &buffer[PASTE_START.len()..];
+    let end = text
+        .windows(PASTE_END.len())
+        .position(|window| window == PASTE_END)?;
+    let paste = String::from_utf8_lossy(&text[..end]).into_owned();
+    Some((
+        PASTE_START.len() + end + PASTE_END.len(),
+        Some(Event::Paste(paste)),
+    ))
+}
+
+/// Parses an OSC sequence, which is `ESC ]` followed by text and terminated by `BEL` or `ESC \`.
+///
+/// Returns `None` if the terminator has not arrived yet.
+fn parse_osc(buffer: &[u8]) -> Option<(usize, Reply)> {
+    let text = &buffer[OSC_START.len()..];
+    let (end, terminator_length) =
+        text.iter()
+            .enumerate()
+            .find_map(|(index, &byte)| match byte {
+                BEL => Some((index, 1)),
+                ESC if text.get(index + 1) == Some(&b'\\') => Some((index, 2)),
+                _ => None,
+            })?;
+    let osc = String::from_utf8_lossy(&text[..end]).into_owned();
+    Some((OSC_START.len() + end + terminator_length, Reply::Osc(osc)))
+}
+
+/// Parses a cursor position report: `ESC [ row ; column R`.
+///
+/// Returns `None` if `buffer` does not start with a complete one.
+fn parse_cursor_position(buffer: &[u8]) -> Option<(usize, Point)> {
+    let text = buffer.strip_prefix(b"\x1b[")?;
+    let end = text
+        .iter()
+        .position(|byte| !matches!(byte, b'0'..=b'9' | b';'))?;
+    if text[end] != b'R' {
+        return None;
+    }
+    match *parse_parameters(&text[..end]) {
+        [row, column] => Some((
+            2 + end + 1,
+            Point {
+                x: column.saturating_sub(1).min(u16::MAX as u32) as u16,
+                y: row.saturating_sub(1).min(u16::MAX as u32) as u16,
+            },
+        )),
+        _ => None,
+    }
+}
+
+/// Parses the numeric parameters of a CSI sequence.
+///
+/// Only the first of the colon-separated subparameters is kept.
+fn parse_parameters(parameters: &[u8]) -> Vec<u32> {
+    parameters
+        .split(|&byte| byte == b';')
+        .map(|parameter| {
+            let parameter = parameter.split(|&byte| byte == b':').next().unwrap_or(&[]);
+            str::from_utf8(parameter)
+                .ok()
+                .and_then(|parameter| parameter.parse().ok())
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Parses a key that is identified by the final byte of a CSI sequence, such as `ESC [ 1 ; 5 A`.
+fn parse_csi_key(final_byte: u8, parameters: &[u32]) -> Option<Event> {
+    let key = match final_byte {
+        b'A' => Key::Up,
+        b'B' => Key::Down,
+        b'C' => Key::Right,
+        b'D' => Key::Left,
+        b'H' => Key::Home,
+        b'F' => Key::End,
+        b'Z' => Key::BackTab,
+        byte @ b'P'..=b'S' => Key::F(byte - b'P' + 1),
+        _ => return None,
+    };
+    Some(key_event(key, parse_modifiers(parameters.get(1))))
+}
+
+/// Parses a key of the form `ESC [ number ; modifiers ~`.
+fn parse_special_key(parameters: &[u32]) -> Option<Event> {
+    let key = match parameters.first()? {
+        // xterm's `modifyOtherKeys` format: `ESC [ 27 ; modifiers ; codepoint ~`
+        27 => {
+            let key = codepoint_key(*parameters.get(2)?)?;
+            return Some(key_event(key, parse_modifiers(parameters.get(1))));
+        }
+        1 | 7 => Key::Home,
+        2 => Key::Insert,
+        3 => Key::Delete,
+        4 | 8 => Key::End,
+        5 => Key::PageUp,
+        6 => Key::PageDown,
+        number @ 11..=15 => Key::F((number - 10) as u8),
+        number @ 17..=21 => Key::F((number - 11) as u8),
+        number @ 23..=26 => Key::F((number - 12) as u8),
+        number @ 28..=29 => Key::F((number - 13) as u8),
+        number @ 31..=34 => Key::F((number - 14) as u8),
+        _ => return None,
+    };
+    Some(key_event(key, parse_modifiers(parameters.get(1))))
+}
+
+/// Parses a key of the form `ESC [ codepoint ; modifiers u`, which is used by the kitty keyboard protocol.
+fn parse_codepoint_key(parameters: &[u32]) -> Option<Event> {
+    let key = codepoint_key(*parameters.first()?)?;
+    Some(key_event(key, parse_modifiers(parameters.get(1))))
+}
+
+fn codepoint_key(codepoint: u32) -> Option<Key> {
+    let key = match codepoint {
+        9 => Key::Tab,
+        13 => Key::Enter,
+        27 => Key::Esc,
+        8 | 127 => Key::Backspace,
+        codepoint => Key::Char(char::from_u32(codepoint)?),
+    };
+    Some(key)
+}
+
+/// Parses a mouse event in the SGR format: `ESC [ < button ; x ; y M` or `m` on release.
+fn parse_sgr_mouse(parameters: &[u8], final_byte: u8) -> Option<Event> {
+    let parameters = parse_parameters(parameters);
+    match (final_byte, parameters.as_slice()) {
+        (b'M', &[button, x, y]) => mouse_event(button, x, y, false),
+        (b'm', &[button, x, y]) => mouse_event(button, x, y, true),
+        _ => None,
+    }
+}
+
+/// Parses a mouse event in the rxvt format: `ESC [ button ; x ; y M`.
+fn parse_rxvt_mouse(parameters: &[u32]) -> Option<Event> {
+    match *parameters {
+        [button, x, y] => mouse_event(button.checked_sub(32)?, x, y, false),
+        _ => None,
+    }
+}
+
+/// Parses a mouse event in the X10 format: `ESC [ M` followed by three bytes.
+fn parse_x10_mouse(buffer: &[u8]) -> Option<(usize, Option<Event>)> {
+    let bytes = buffer.get(3..6)?;
+    let parameters: Vec<u32> = bytes
+        .iter()
+        .map(|&byte| (byte as u32).saturating_sub(32))
+        .collect();
+    Some((
+        6,
+        mouse_event(parameters[0], parameters[1], parameters[2], false),
+    ))
+}
+
+/// Makes a mouse event out of the button code and the one-based coordinates all mouse formats share.
+fn mouse_event(code: u32, x: u32, y: u32, released: bool) -> Option<Event> {
+    let button = match code & 0b11 {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    };
+    let moved = code & 0b10_0000 != 0;
+    let scrolled = code & 0b100_0000 != 0;
+
+    let kind = if scrolled {
+        match code & 0b11 {
+            0 => MouseEventKind::ScrollUp,
+            1 => MouseEventKind::ScrollDown,
+            _ => return None,
+        }
+    } else if moved {
+        match button {
+            Some(button) => MouseEventKind::Drag(button),
+            None => MouseEventKind::Move,
+        }
+    } else if released {
+        MouseEventKind::Release(button?)
+    } else {
+        match button {
+            Some(button) => MouseEventKind::Press(button),
+            // The formats other than SGR do not say which button was released
+            None => MouseEventKind::Release(MouseButton::Left),
+        }
+    };
+
+    let mut modifiers = Modifiers::empty();
+    modifiers.set(Modifiers::SHIFT, code & 0b100 != 0);
+    modifiers.set(Modifiers::ALT, code & 0b1000 != 0);
+    modifiers.set(Modifiers::CTRL, code & 0b1_0000 != 0);
+
+    let coordinate = |coordinate: u32| coordinate.saturating_sub(1).min(u16::MAX as u32) as u16;
+    let point = Point {
+        x: coordinate(x),
+        y: coordinate(y),
+    };
+
+    Some(Event::Mouse(MouseEvent {
+        kind,
+        point,
+        modifiers,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn parse_all(bytes: &[u8]) -> Vec<Event> {
+        let mut parser = Parser::default();
+        parser.advance(bytes, false);
+        std::iter::from_fn(|| parser.next()).collect()
+    }
+
+    fn key_events(bytes: &[u8]) -> Vec<KeyEvent> {
+        parse_all(bytes)
+            .into_iter()
+            .map(|event| match event {
+                Event::Key(key_event) => key_event,
+                event => panic!("expected a key but got {:?}", event),
+            })
+            .collect()
+    }
+
+    fn keys(bytes: &[u8]) -> Vec<Key> {
+        key_events(bytes)
+            .into_iter()
+            .map(|key_event| key_event.key)
+            .collect()
+    }
+
+    fn mouse(bytes: &[u8]) -> (MouseEventKind, Point) {
+        match parse_all(bytes).as_slice() {
+            [Event::Mouse(MouseEvent { kind, point, .. })] => (*kind, *point),
+            events => panic!("expected a mouse event but got {:?}", events),
+        }
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(keys(b"a"), [Key::Char('a')]);
+        assert_eq!(keys("ü日".as_bytes()), [Key::Char('ü'), Key::Char('日')]);
+        assert_eq!(keys(b"\r\t\x7f"), [Key::Enter, Key::Tab, Key::Backspace]);
+        assert_eq!(keys(b"\x1b"), [Key::Esc]);
+        assert_eq!(keys(b"\x1b\x1b"), [Key::Esc, Key::Esc]);
+        assert_eq!(
+            keys(b"\x1b[A\x1b[B\x1b[C\x1b[D"),
+            [Key::Up, Key::Down, Key::Right, Key::Left]
+        );
+        assert_eq!(keys(b"\x1bOA\x1bOP"), [Key::Up, Key::F(1)]);
+        assert_eq!(
+            keys(b"\x1b[11~\x1b[15~\x1b[17~\x1b[24~"),
+            [Key::F(1), Key::F(5), Key::F(6), Key::F(12)]
+        );
+        assert_eq!(
+            keys(b"\x1b[26~\x1b[28~\x1b[34~"),
+            [Key::F(14), Key::F(15), Key::F(20)]
+        );
+        assert_eq!(
+            keys(b"\x1b[H\x1b[F\x1bOH\x1bOF\x1b[1~\x1b[4~\x1b[7~\x1b[8~"),
+            [
+                Key::Home,
+                Key::End,
+                Key::Home,
+                Key::End,
+                Key::Home,
+                Key::End,
+                Key::Home,
+                Key::End
+            ]
+        );
+        assert_eq!(
+            keys(b"\x1b[5~\x1b[6~\x1b[2~\x1b[3~\x1b[Z\x00"),
+            [
+                Key::PageUp,
+                Key::PageDown,
+                Key::Insert,
+                Key::Delete,
+                Key::BackTab,
+                Key::Null
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_incomplete() {
+        let mut parser = Parser::default();
+
+        parser.advance(b"\x1b", true);
+        assert!(parser.next().is_none());
+        parser.advance(b"[", true);
+        assert!(parser.next().is_none());
+        parser.advance(b"A", false);
+        assert_eq!(parser.next(), Some(key_event(Key::Up, Modifiers::empty())));
+
+        parser.advance(&"日".as_bytes()[..1], false);
+        assert!(parser.next().is_none());
+        parser.advance(&"日".as_bytes()[1..], false);
+        assert_eq!(
+            parser.next(),
+            Some(key_event(Key::Char('日'), Modifiers::empty()))
+        );
+    }
+
+    #[test]
+    fn test_parse_modifiers() {
+        let key = KeyEvent::new;
+
+        assert_eq!(
+            key_events(b"a\x01\x1ba\x1b\x01"),
+            [
+                key(Key::Char('a'), Modifiers::empty()),
+                key(Key::Char('a'), Modifiers::CTRL),
+                key(Key::Char('a'), Modifiers::ALT),
+                key(Key::Char('a'), Modifiers::ALT | Modifiers::CTRL),
+            ]
+        );
+        assert_eq!(
+            key_events(b"\x1b[1;5A\x1b[1;2P\x1b[15;3~"),
+            [
+                key(Key::Up, Modifiers::CTRL),
+                key(Key::F(1), Modifiers::SHIFT),
+                key(Key::F(5), Modifiers::ALT),
+            ]
+        );
+        assert_eq!(
+            key_events(b"\x1b[115;9u\x1b[97:65;17u\x1b[27;33;13~"),
+            [
+                key(Key::Char('s'), Modifiers::SUPER),
+                key(Key::Char('a'), Modifiers::HYPER),
+                key(Key::Enter, Modifiers::META),
+            ]
+        );
+
+        match parse_all(b"\x1b[<20;1;1M").as_slice() {
+            [Event::Mouse(mouse_event)] => {
+                assert_eq!(mouse_event.modifiers, Modifiers::SHIFT | Modifiers::CTRL)
+            }
+            events => panic!("expected a mouse event but got {:?}", events),
+        }
+    }
+
+    #[test]
+    fn test_parse_paste() {
+        assert_eq!(
+            parse_all(b"a\x1b[200~b\x1b[A\r\nc\x1b[201~d"),
+            [
+                key_event(Key::Char('a'), Modifiers::empty()),
+                Event::Paste("b\x1b[A\r\nc".to_string()),
+                key_event(Key::Char('d'), Modifiers::empty()),
+            ]
+        );
+
+        let mut parser = Parser::default();
+        parser.advance(b"\x1b[200~a", false);
+        assert_eq!(parser.next(), None);
+        parser.advance(b"b\x1b[201", false);
+        assert_eq!(parser.next(), None);
+        parser.advance(b"~", false);
+        assert_eq!(parser.next(), Some(Event::Paste("ab".to_string())));
+    }
+
+    #[test]
+    fn test_parse_osc() {
+        let mut parser = Parser::default();
+        parser.expect_osc(true);
+
+        parser.advance(b"a\x1b]11;rgb:ffff/0000/0000\x07b\x1b]10;rgb:0/0", true);
+        assert_eq!(
+            parser.next_reply(),
+            Some(Reply::Osc("11;rgb:ffff/0000/0000".into()))
+        );
+        assert!(parser.next_reply().is_none());
+        parser.advance(b"/0\x1b\\", false);
+        assert_eq!(parser.next_reply(), Some(Reply::Osc("10;rgb:0/0/0".into())));
+        let keys: Vec<_> = std::iter::from_fn(|| parser.next()).collect();
+        assert_eq!(
+            keys,
+            [
+                key_event(Key::Char('a'), Modifiers::empty()),
+                key_event(Key::Char('b'), Modifiers::empty())
+            ]
+        );
+
+        parser.advance(b"\x1b]1", false);
+        assert!(parser.next().is_none());
+        parser.expect_osc(false);
+        assert_eq!(
+            parser.next(),
+            Some(key_event(Key::Char(']'), Modifiers::ALT))
+        );
+        assert_eq!(
+            parser.next(),
+            Some(key_event(Key::Char('1'), Modifiers::empty()))
+        );
+
+        assert_eq!(
+            key_events(b"\x1b]"),
+            [KeyEvent::new(Key::Char(']'), Modifiers::ALT)]
+        );
+        parser.advance(b"\x1b]ab", false);
+        parser.advance(b"c\r", false);
+        let keys: Vec<_> = std::iter::from_fn(|| parser.next()).collect();
+        assert_eq!(keys.len(), 5);
+        assert_eq!(keys[0], key_event(Key::Char(']'), Modifiers::ALT));
+        assert_eq!(keys[4], key_event(Key::Enter, Modifiers::empty()));
+    }
+
+    #[test]
+    fn test_parse_cursor_position() {
+        let mut parser = Parser::default();
+
+        parser.advance(b"\x1b[1;2R", false);
+        assert!(parser.next_reply().is_none());
+        assert_eq!(parser.next(), Some(key_event(Key::F(3), Modifiers::SHIFT)));
+
+        parser.expect_cursor_position();
+        parser.advance(b"a\x1b[5;1", true);
+        assert!(parser.next_reply().is_none());
+        parser.advance(b"0R\x1b[A", false);
+        assert_eq!(
+            parser.next_reply(),
+            Some(Reply::CursorPosition(Point { x: 9, y: 4 }))
+        );
+        let keys: Vec<_> = std::iter::from_fn(|| parser.next()).collect();
+        assert_eq!(
+            keys,
+            [
+                key_event(Key::Char('a'), Modifiers::empty()),
+                key_event(Key::Up, Modifiers::empty())
+            ]
+        );
+
+        // Only one reply was expected
+        parser.advance(b"\x1b[1;2R", false);
+        assert!(parser.next_reply().is_none());
+        assert_eq!(parser.next(), Some(key_event(Key::F(3), Modifiers::SHIFT)));
+
+        // A reply that was given up on is expected until its deadline
+        let now = Instant::now();
+        parser.expect_cursor_position();
+        parser.give_up_cursor_position(now);
+        parser.expect_cursor_position();
+        parser.expire_cursor_positions(now);
+        parser.advance(b"\x1b[1;2R", false);
+        assert!(parser.next_reply().is_some());
+        parser.expect_cursor_position();
+        parser.give_up_cursor_position(now + Duration::from_secs(1));
+        parser.expire_cursor_positions(now);
+        parser.advance(b"\x1b[1;2R", false);
+        assert!(parser.next_reply().is_some());
+        parser.advance(b"\x1b[1;2R", false);
+        assert_eq!(parser.next(), Some(key_event(Key::F(3), Modifiers::SHIFT)));
+    }
+
+    #[test]
+    fn test_parse_focus() {
+        assert_eq!(
+            parse_all(b"\x1b[I\x1b[Oa"),
+            [
+                Event::FocusGained,
+                Event::FocusLost,
+                key_event(Key::Char('a'), Modifiers::empty()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_unsupported() {
+        assert_eq!(keys(b"\x1b[99za"), [Key::Char('a')]);
+        assert_eq!(keys(b"\x1b[\x01a"), [Key::Char('a'), Key::Char('a')]);
+    }
+
+    #[test]
+    fn test_parse_mouse() {
+        let point = Point { x: 9, y: 19 };
+
+        assert_eq!(
+            mouse(b"\x1b[<0;10;20M"),
+            (MouseEventKind::Press(MouseButton::Left), point)
+        );
+        assert_eq!(
+            mouse(b"\x1b[<2;10;20m"),
+            (MouseEventKind::Release(MouseButton::Right), point)
+        );
+        assert_eq!(
+            mouse(b"\x1b[<33;10;20M"),
+            (MouseEventKind::Drag(MouseButton::Middle), point)
+        );
+        assert_eq!(mouse(b"\x1b[<35;10;20M"), (MouseEventKind::Move, point));
+        assert_eq!(mouse(b"\x1b[<64;10;20M"), (MouseEventKind::ScrollUp, point));
+        assert_eq!(
+            mouse(b"\x1b[<65;10;20M"),
+            (MouseEventKind::ScrollDown, point)
+        );
+        assert_eq!(
+            mouse(b"\x1b[32;10;20M"),
+            (MouseEventKind::Press(MouseButton::Left), point)
+        );
+        assert_eq!(
+            mouse(&[ESC, b'[', b'M', 32 + 1, 32 + 10, 32 + 20]),
+            (MouseEventKind::Press(MouseButton::Middle), point)
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! The appearance of text.
+
+use crate::util::Color;
+use bitflags::bitflags;
+
+bitflags! {
+    /// A set of text attributes.
+    ///
+    /// At most one of the underline attributes should be set at a time.
+    #[derive(Default)]
+    pub struct Attributes: u16 {
+        const BOLD = 1 << 0;
+        /// Also known as faint.
+        const DIM = 1 << 1;
+        const ITALIC = 1 << 2;
+        const UNDERLINE = 1 << 3;
+        const DOUBLE_UNDERLINE = 1 << 4;
+        const CURLY_UNDERLINE = 1 << 5;
+        const DOTTED_UNDERLINE = 1 << 6;
+        const DASHED_UNDERLINE = 1 << 7;
+        const BLINK = 1 << 8;
+        /// Swaps the foreground and background color.
+        const REVERSE = 1 << 9;
+        const HIDDEN = 1 << 10;
+        const STRIKETHROUGH = 1 << 11;
+        const OVERLINE = 1 << 12;
+
+        const UNDERLINES = Self::UNDERLINE.bits
+            | Self::DOUBLE_UNDERLINE.bits
+            | Self::CURLY_UNDERLINE.bits
+            | Self::DOTTED_UNDERLINE.bits
+            | Self::DASHED_UNDERLINE.bits;
+    }
+}
+
+impl Attributes {
+    /// Attributes that can only be disabled together because they share the same escape sequence for that.
+    pub(crate) const GROUPS: [Attributes; 2] = [
+        Attributes::from_bits_truncate(Attributes::BOLD.bits | Attributes::DIM.bits),
+        Attributes::UNDERLINES,
+    ];
+
+    /// Returns the SGR parameters that enable each of the attributes.
+    pub(crate) fn enable_parameters(self) -> impl Iterator<Item = &'static str> {
+        const PARAMETERS: [(Attributes, &str); 13] = [
+            (Attributes::BOLD, "1"),
+            (Attributes::DIM, "2"),
+            (Attributes::ITALIC, "3"),
+            (Attributes::UNDERLINE, "4"),
+            (Attributes::DOUBLE_UNDERLINE, "4:2"),
+            (Attributes::CURLY_UNDERLINE, "4:3"),
+            (Attributes::DOTTED_UNDERLINE, "4:4"),
+            (Attributes::DASHED_UNDERLINE, "4:5"),
+            (Attributes::BLINK, "5"),
+            (Attributes::REVERSE, "7"),
+            (Attributes::HIDDEN, "8"),
+            (Attributes::STRIKETHROUGH, "9"),
+            (Attributes::OVERLINE, "53"),
+        ];
+
+        PARAMETERS
+            .iter()
+            .filter(move |(attribute, _)| self.contains(*attribute))
+            .map(|(_, parameter)| *parameter)
+    }
+
+    /// Returns the SGR parameters that disable each of the attributes, without duplicates.
+    pub(crate) fn disable_parameters(self) -> impl Iterator<Item = &'static str> {
+        const PARAMETERS: [(Attributes, &str); 8] = [
+            (Attributes::GROUPS[0], "22"),
+            (Attributes::ITALIC, "23"),
+            (Attributes::UNDERLINES, "24"),
+            (Attributes::BLINK, "25"),
+            (Attributes::REVERSE, "27"),
+            (Attributes::HIDDEN, "28"),
+            (Attributes::STRIKETHROUGH, "29"),
+            (Attributes::OVERLINE, "55"),
+        ];
+
+        PARAMETERS
+            .iter()
+            .filter(move |(attributes, _)| self.intersects(*attributes))
+            .map(|(_, parameter)| *parameter)
+    }
+}
+
+/// The kinds of underlines, each of which corresponds to one of the underline [`Attributes`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum UnderlineStyle {
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+
+impl UnderlineStyle {
+    pub fn attribute(self) -> Attributes {
+        match self {
+            UnderlineStyle::Single => Attributes::UNDERLINE,
+            UnderlineStyle::Double => Attributes::DOUBLE_UNDERLINE,
+            UnderlineStyle::Curly => Attributes::CURLY_UNDERLINE,
+            UnderlineStyle::Dotted => Attributes::DOTTED_UNDERLINE,
+            UnderlineStyle::Dashed => Attributes::DASHED_UNDERLINE,
+        }
+    }
+}
+
+/// The colors and attributes of text.
+///
+/// The default style uses the terminal's default colors and no attributes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Style {
+    /// If this is `None`, the text has the terminal's default color.
+    pub foreground: Option<Color>,
+    /// If this is `None`, the terminal's default background color is used.
+    pub background: Option<Color>,
+    /// If this is `None`, underlines have the color of the text.
+    pub underline_color: Option<Color>,
+    pub attributes: Attributes,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            foreground: None,
+            background: None,
+            underline_color: None,
+            attributes: Attributes::empty(),
+        }
+    }
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn foreground(mut self, foreground: Color) -> Self {
+        self.foreground = Some(foreground);
+        self
+    }
+
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    pub fn underline_color(mut self, underline_color: Color) -> Self {
+        self.underline_color = Some(underline_color);
+        self
+    }
+
+    /// Adds `attributes` to the attributes of this style.
+    pub fn attributes(mut self, attributes: Attributes) -> Self {
+        self.attributes |= attributes;
+        self
+    }
+
+    /// Replaces the underline of this style with one of `underline_style`.
+    pub fn underline(mut self, underline_style: UnderlineStyle) -> Self {
+        self.attributes -= Attributes::UNDERLINES;
+        self.attributes |= underline_style.attribute();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameters() {
+        let attributes = Attributes::BOLD | Attributes::CURLY_UNDERLINE | Attributes::OVERLINE;
+        assert_eq!(
+            attributes.enable_parameters().collect::<Vec<_>>(),
+            ["1", "4:3", "53"]
+        );
+        assert_eq!(
+            (attributes | Attributes::DIM)
+                .disable_parameters()
+                .collect::<Vec<_>>(),
+            ["22", "24", "55"]
+        );
+    }
+}
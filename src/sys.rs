@@ -1,9 +1,11 @@
-#[cfg(not(target_os = "redox"))]
-mod other;
-#[cfg(target_os = "redox")]
-mod redox;
+//! Platform-specific functionality shared by the backends.
 
-// #[cfg(target_os = "windows")]
-// mod other;
-// #[cfg(not(target_os = "windows"))]
-// mod redox;
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(unix)]
+pub(crate) use self::unix::{restore, save_mode, Tty};
+#[cfg(windows)]
+pub(crate) use self::windows::{restore, save_mode};
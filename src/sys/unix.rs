@@ -0,0 +1,156 @@
+//! Reading input from and restoring the terminal on Unix-like operating systems.
+
+use signal_hook::{consts::SIGWINCH, low_level, SigId};
+use std::{
+    fs::File,
+    io::{self, Read},
+    mem,
+    os::unix::{
+        io::{AsRawFd, RawFd},
+        net::UnixStream,
+    },
+    sync::OnceLock,
+    time::Duration,
+};
+
+/// The mode the terminal was in before it was changed, for example by enabling raw mode.
+static ORIGINAL_MODE: OnceLock<libc::termios> = OnceLock::new();
+
+/// The terminal's input side.
+///
+/// This is the standard input stream if that is a terminal and `/dev/tty` otherwise.
+#[derive(Debug)]
+pub(crate) struct Tty {
+    file: Option<File>,
+    /// Receives a byte every time the terminal is resized.
+    resize_receiver: UnixStream,
+    resize_signal: SigId,
+}
+
+impl Tty {
+    pub fn new() -> io::Result<Self> {
+        let file = if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+            None
+        } else {
+            Some(File::open("/dev/tty")?)
+        };
+
+        let (resize_receiver, resize_sender) = UnixStream::pair()?;
+        resize_receiver.set_nonblocking(true)?;
+        resize_sender.set_nonblocking(true)?;
+        // The sending end is closed once the signal is unregistered
+        let resize_signal = low_level::pipe::register(SIGWINCH, resize_sender)?;
+
+        Ok(Self {
+            file,
+            resize_receiver,
+            resize_signal,
+        })
+    }
+
+    fn fd(&self) -> RawFd {
+        self.file
+            .as_ref()
+            .map_or(libc::STDIN_FILENO, |file| file.as_raw_fd())
+    }
+
+    /// Reads input into `buffer`, waiting at most `timeout` or forever if it is `None`.
+    ///
+    /// Returns `0` if no input arrived in time or if the terminal was resized in the meantime.
+    pub fn read(&mut self, buffer: &mut [u8], timeout: Option<Duration>) -> io::Result<usize> {
+        let mut fds = [
+            libc::pollfd {
+                fd: self.fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: self.resize_receiver.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        let timeout = timeout.map_or(-1, |timeout| {
+            // Round up so that a short timeout does not turn into no timeout at all
+            let milliseconds = timeout.as_nanos().div_ceil(1_000_000);
+            milliseconds.min(libc::c_int::MAX as u128) as libc::c_int
+        });
+
+        if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) } < 0 {
+            return Self::interrupted(io::Error::last_os_error());
+        }
+
+        if fds[1].revents & libc::POLLIN != 0 {
+            let mut signals = [0; 16];
+            while let Ok(count) = self.resize_receiver.read(&mut signals) {
+                if count == 0 {
+                    break;
+                }
+            }
+            return Ok(0);
+        }
+
+        if fds[0].revents & libc::POLLIN != 0 {
+            let count = unsafe { libc::read(self.fd(), buffer.as_mut_ptr().cast(), buffer.len()) };
+            return match count {
+                count if count < 0 => Self::interrupted(io::Error::last_os_error()),
+                0 => Err(io::ErrorKind::UnexpectedEof.into()),
+                count => Ok(count as usize),
+            };
+        }
+
+        if fds[0].revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(0)
+    }
+
+    fn interrupted(err: io::Error) -> io::Result<usize> {
+        if err.kind() == io::ErrorKind::Interrupted {
+            Ok(0)
+        } else {
+            Err(err)
+        }
+    }
+}
+
+impl Drop for Tty {
+    fn drop(&mut self) {
+        low_level::unregister(self.resize_signal);
+    }
+}
+
+/// Saves the current mode of the terminal so that [`restore`] can return to it.
+///
+/// Only the first call has an effect.
+pub(crate) fn save_mode() {
+    let mut termios = unsafe { mem::zeroed() };
+    if unsafe { libc::tcgetattr(libc::STDOUT_FILENO, &mut termios) } == 0 {
+        let _ = ORIGINAL_MODE.set(termios);
+    }
+}
+
+/// Writes `output` to the terminal and returns to the mode saved by [`save_mode`] if `mode` is `true`.
+///
+/// This is meant for when the program is panicking, so `io::Stdout` is not used because it might be locked
+/// and errors are ignored.
+pub(crate) fn restore(mut output: &[u8], mode: bool) {
+    while !output.is_empty() {
+        let count =
+            unsafe { libc::write(libc::STDOUT_FILENO, output.as_ptr().cast(), output.len()) };
+        if count < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+            continue;
+        }
+        if count <= 0 {
+            break;
+        }
+        output = &output[count as usize..];
+    }
+
+    if mode {
+        if let Some(termios) = ORIGINAL_MODE.get() {
+            unsafe { libc::tcsetattr(libc::STDOUT_FILENO, libc::TCSANOW, termios) };
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Restoring the console on Windows.
+
+use std::{ptr, sync::OnceLock};
+use winapi::{
+    shared::minwindef::DWORD,
+    um::{
+        consoleapi::{GetConsoleMode, SetConsoleMode},
+        fileapi::{CreateFileW, WriteFile, OPEN_EXISTING},
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        processenv::GetStdHandle,
+        winbase::STD_OUTPUT_HANDLE,
+        winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE, HANDLE},
+    },
+};
+
+/// The mode the console's input was in before it was changed, for example by enabling raw mode.
+static ORIGINAL_MODE: OnceLock<DWORD> = OnceLock::new();
+
+/// Opens the console's input buffer, whose mode is the one raw mode changes.
+///
+/// The handle has to be closed with `CloseHandle`.
+fn open_input() -> Option<HANDLE> {
+    let name: Vec<u16> = "CONIN$\0".encode_utf16().collect();
+    let handle = unsafe {
+        CreateFileW(
+            name.as_ptr(),
+            GENERIC_READ | GENERIC_WRITE,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            ptr::null_mut(),
+            OPEN_EXISTING,
+            0,
+            ptr::null_mut(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        None
+    } else {
+        Some(handle)
+    }
+}
+
+/// Saves the current mode of the console so that [`restore`] can return to it.
+///
+/// Only the first call has an effect.
+pub(crate) fn save_mode() {
+    if let Some(input) = open_input() {
+        let mut mode = 0;
+        if unsafe { GetConsoleMode(input, &mut mode) } != 0 {
+            let _ = ORIGINAL_MODE.set(mode);
+        }
+        unsafe { CloseHandle(input) };
+    }
+}
+
+/// Writes `output` to the console and returns to the mode saved by [`save_mode`] if `mode` is `true`.
+///
+/// This is meant for when the program is panicking, so `io::Stdout` is not used because it might be locked
+/// and errors are ignored.
+pub(crate) fn restore(mut output: &[u8], mode: bool) {
+    let handle = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+    if !handle.is_null() && handle != INVALID_HANDLE_VALUE {
+        while !output.is_empty() {
+            let length = output.len().min(DWORD::MAX as usize) as DWORD;
+            let mut count = 0;
+            let written = unsafe {
+                WriteFile(
+                    handle,
+                    output.as_ptr().cast(),
+                    length,
+                    &mut count,
+                    ptr::null_mut(),
+                )
+            };
+            if written == 0 || count == 0 {
+                break;
+            }
+            output = &output[count as usize..];
+        }
+    }
+
+    if mode {
+        if let Some(original_mode) = ORIGINAL_MODE.get() {
+            if let Some(input) = open_input() {
+                unsafe {
+                    SetConsoleMode(input, *original_mode);
+                    CloseHandle(input);
+                }
+            }
+        }
+    }
+}
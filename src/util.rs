@@ -1,4 +1,8 @@
-use std::{convert::TryFrom, fmt};
+mod parse;
+
+pub(crate) use self::parse::parse_x11;
+pub use self::parse::ParseColorError;
+use std::{fmt, str::FromStr};
 
 #[derive(Clone, Copy, PartialEq, Eq, Default, Hash, Debug)]
 pub struct Point {
@@ -35,7 +39,7 @@ impl Size {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Hash, Debug)]
 pub enum Color {
     // 4-bit colors
     DarkRed,
@@ -45,6 +49,7 @@ pub enum Color {
     DarkMagenta,
     DarkCyan,
     /// A terminal's default background color.
+    #[default]
     Black,
     /// A terminal's default foreground color.
     Gray,
@@ -66,122 +71,374 @@ pub enum Color {
     },
 }
 
-impl Default for Color {
-    fn default() -> Self {
-        Color::Black
+/// The 4-bit colors in the order of the terminal's palette.
+const FOUR_BIT_COLORS: [Color; Color::FOUR_BIT_COLOR_COUNT as usize] = [
+    Color::Black,
+    Color::DarkRed,
+    Color::DarkGreen,
+    Color::DarkYellow,
+    Color::DarkBlue,
+    Color::DarkMagenta,
+    Color::DarkCyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+/// The RGB values of [`FOUR_BIT_COLORS`] in xterm's default palette.
+/// Terminals differ here, but these are close enough to find the nearest color.
+const FOUR_BIT_RGB: [(u8, u8, u8); Color::FOUR_BIT_COLOR_COUNT as usize] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The values each component can have in the 6×6×6 color cube of the 8-bit colors.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The index of the first color of the color cube in the 8-bit colors.
+const CUBE_START: u8 = Color::FOUR_BIT_COLOR_COUNT;
+/// The index of the first grayscale color in the 8-bit colors.
+const GRAYSCALE_START: u8 = u8::MAX - Color::GRAYSCALE_COLOR_COUNT + 1;
+
+/// Returns the hue in degrees of an RGB color with components from 0 to 1 and the largest and smallest of them.
+fn hue((r, g, b): (f32, f32, f32), max: f32, min: f32) -> f32 {
+    let chroma = max - min;
+    if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
     }
 }
 
+/// Builds an RGB color from a hue in degrees, a chroma and the amount `m` added to each component.
+fn from_chroma(hue: f32, chroma: f32, m: f32) -> Color {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let component = |value: f32| ((value + m) * 255.0).round() as u8;
+    Color::Rgb {
+        r: component(r),
+        g: component(g),
+        b: component(b),
+    }
+}
+
+/// Converts an sRGB component from 0 to 255 into linear light from 0 to 1.
+fn to_linear(component: u8) -> f32 {
+    let value = component as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts linear light from 0 to 1 into an sRGB component from 0 to 255.
+fn from_linear(value: f32) -> u8 {
+    let value = if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (value * 255.0).round() as u8
+}
+
+/// Returns the squared distance between two RGB colors.
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let square = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    square(r1, r2) + square(g1, g2) + square(b1, b2)
+}
+
 impl Color {
     pub const GRAYSCALE_COLOR_COUNT: u8 = 24;
     pub const FOUR_BIT_COLOR_COUNT: u8 = 8 * 2;
 
+    /// Returns the complement of this color. Inverting a color twice returns the original.
+    ///
+    /// - The 4-bit colors and the 8-bit colors with the same indices invert their red, green and blue
+    ///   and their brightness, so that [`Color::Black`] and [`Color::White`] swap,
+    ///   [`Color::DarkRed`] and [`Color::Cyan`] swap, and [`Color::Gray`] and [`Color::DarkGray`] swap.
+    /// - 8-bit colors in the 6×6×6 color cube invert each component within the cube.
+    /// - 8-bit grayscale colors are mirrored within the grayscale.
+    /// - RGB colors invert each component.
     pub fn invert(&self) -> Self {
-        use Color::*;
-
-        match self {
-            Byte(mut byte) => {
-                if byte >= u8::MAX - Color::GRAYSCALE_COLOR_COUNT {
-                    byte = u8::MAX - byte;
-                    byte += u8::MAX - Color::GRAYSCALE_COLOR_COUNT - 1;
-                } else {
-                    byte = u8::MAX - byte - Color::FOUR_BIT_COLOR_COUNT + 1;
-                }
-                Byte(byte)
+        match *self {
+            Color::Byte(byte) if byte < CUBE_START => {
+                Color::Byte(Color::FOUR_BIT_COLOR_COUNT - 1 - byte)
+            }
+            Color::Byte(byte) if byte < GRAYSCALE_START => {
+                Color::Byte((GRAYSCALE_START - 1) - (byte - CUBE_START))
             }
-            Rgb { r, g, b } => Rgb {
+            Color::Byte(byte) => Color::Byte(GRAYSCALE_START + (u8::MAX - byte)),
+            Color::Rgb { r, g, b } => Color::Rgb {
                 r: u8::MAX - r,
                 g: u8::MAX - g,
                 b: u8::MAX - b,
             },
-            Black | DarkGray => White,
-            _ => Black,
+            color => {
+                let index = color.four_bit_index().unwrap();
+                FOUR_BIT_COLORS[(Color::FOUR_BIT_COLOR_COUNT - 1 - index) as usize]
+            }
         }
     }
 
-    /// Tries to parse the input into an RGB color.
-    /// It can parse the following RGB notations:
-    ///
-    /// - [X] 8-bit,       e.g. (255, 0, 0)
-    /// - [X] Hexadecimal, e.g. #FF0000
-    /// - [ ] Float,       e.g. (1.0, 0.0, 0.0)
-    /// - [ ] Percentage,  e.g. (100%, 0%, 0%)
-    ///
-    /// See <https://en.wikipedia.org/wiki/RGB_color_model> for more information.
+    /// Returns the index of a 4-bit color in the terminal's palette.
+    pub(crate) fn four_bit_index(self) -> Option<u8> {
+        use Color::*;
+
+        let index = match self {
+            Black => 0,
+            DarkRed => 1,
+            DarkGreen => 2,
+            DarkYellow => 3,
+            DarkBlue => 4,
+            DarkMagenta => 5,
+            DarkCyan => 6,
+            Gray => 7,
+            DarkGray => 8,
+            Red => 9,
+            Green => 10,
+            Yellow => 11,
+            Blue => 12,
+            Magenta => 13,
+            Cyan => 14,
+            White => 15,
+            Byte(_) | Rgb { .. } => return None,
+        };
+        Some(index)
+    }
+
+    /// Returns the red, green and blue components of this color.
     ///
-    pub fn from_rgb(string: &str) -> Option<Color> {
-        let mut r: Option<u8> = None;
-        let mut g: Option<u8> = None;
-        let mut b: Option<u8> = None;
-
-        let mut component = &mut r;
-
-        let mut digits_in_a_row = 0;
-        let mut index = 0;
-        for char in string.chars() {
-            match char {
-                '0'..='9' => {
-                    if let Some(byte) = char.to_digit(10) {
-                        *component = if let Some(component) = *component {
-                            Some(
-                                u8::try_from(component as usize * 10 + byte as usize)
-                                    .unwrap_or(u8::MAX),
-                            )
-                        } else {
-                            Some(byte as u8)
-                        };
-                    };
-                    digits_in_a_row += 1;
-                }
-                _ if char.is_ascii_hexdigit() => {
-                    if let Some(color) = Self::from_hex(&string[index..]) {
-                        return Some(color);
-                    }
-                    digits_in_a_row += 1;
-                }
-                _ => {
-                    component = match (r, g, b) {
-                        (None, None, None) => &mut r,
-                        (Some(_), None, None) => &mut g,
-                        (Some(_), Some(_), None) => &mut b,
-                        (Some(r), Some(g), Some(b)) => return Some(Color::Rgb { r, g, b }),
-                        _ => unreachable!(),
-                    };
-                    digits_in_a_row = 0;
-                }
+    /// For colors other than [`Color::Rgb`], they are the ones in xterm's default palette.
+    /// Terminals can be configured to use other values, so they are approximate.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Rgb { r, g, b } => (r, g, b),
+            Color::Byte(byte) if byte < CUBE_START => FOUR_BIT_RGB[byte as usize],
+            Color::Byte(byte) if byte < GRAYSCALE_START => {
+                let index = byte - CUBE_START;
+                (
+                    CUBE_LEVELS[(index / 36) as usize],
+                    CUBE_LEVELS[(index / 6 % 6) as usize],
+                    CUBE_LEVELS[(index % 6) as usize],
+                )
             }
+            Color::Byte(byte) => {
+                let level = 8 + (byte - GRAYSCALE_START) * 10;
+                (level, level, level)
+            }
+            color => FOUR_BIT_RGB[color.four_bit_index().unwrap() as usize],
+        }
+    }
 
-            index += 1;
+    /// Converts this color into a [`Color::Rgb`]. See [`Color::rgb`].
+    pub fn to_rgb(self) -> Color {
+        let (r, g, b) = self.rgb();
+        Color::Rgb { r, g, b }
+    }
+
+    /// Converts an RGB color into the nearest color of the color cube or grayscale of the 8-bit colors.
+    ///
+    /// Other colors are returned unchanged.
+    pub fn to_byte(self) -> Color {
+        let rgb = match self {
+            Color::Rgb { r, g, b } => (r, g, b),
+            color => return color,
+        };
 
-            if digits_in_a_row == 6 && index >= digits_in_a_row {
-                if let Some(color) = Self::from_hex(&string[index - digits_in_a_row..]) {
-                    return Some(color);
-                }
+        let nearest_level = |component: u8| {
+            (0..CUBE_LEVELS.len())
+                .min_by_key(|&index| (CUBE_LEVELS[index] as i32 - component as i32).abs())
+                .unwrap() as u8
+        };
+        let cube = CUBE_START
+            + nearest_level(rgb.0) * 36
+            + nearest_level(rgb.1) * 6
+            + nearest_level(rgb.2);
+
+        let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+        let grayscale = GRAYSCALE_START
+            + (average.saturating_sub(3) / 10).min(Color::GRAYSCALE_COLOR_COUNT as u32 - 1) as u8;
+
+        [cube, grayscale]
+            .iter()
+            .map(|&byte| Color::Byte(byte))
+            .min_by_key(|color| distance(color.rgb(), rgb))
+            .unwrap()
+    }
+
+    /// Converts an 8-bit or RGB color into the nearest of the 16 named 4-bit colors.
+    ///
+    /// 4-bit colors are returned unchanged.
+    pub fn to_four_bit(self) -> Color {
+        match self {
+            Color::Byte(byte) if byte < CUBE_START => FOUR_BIT_COLORS[byte as usize],
+            Color::Byte(_) | Color::Rgb { .. } => {
+                let rgb = self.rgb();
+                *FOUR_BIT_COLORS
+                    .iter()
+                    .min_by_key(|color| distance(color.rgb(), rgb))
+                    .unwrap()
             }
+            color => color,
         }
+    }
 
-        match (r, g, b) {
-            (Some(r), None, None) => Some(Color::Rgb { r, g: 0, b: 0 }),
-            (Some(r), Some(g), None) => Some(Color::Rgb { r, g, b: 0 }),
-            (Some(r), Some(g), Some(b)) => Some(Color::Rgb { r, g, b }),
-            _ => None,
-        }
+    /// Returns the red, green and blue components of this color from 0 to 1.
+    fn rgb_fractions(self) -> (f32, f32, f32) {
+        let (r, g, b) = self.rgb();
+        (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
     }
 
-    pub fn from_hex(string: &str) -> Option<Color> {
-        if let (Some(r), Some(g), Some(b)) = (string.get(..2), string.get(2..4), string.get(4..6)) {
-            let r = u8::from_str_radix(r, 16);
-            let g = u8::from_str_radix(g, 16);
-            let b = u8::from_str_radix(b, 16);
-            if let (Ok(r), Ok(g), Ok(b)) = (r, g, b) {
-                Some(Color::Rgb { r, g, b })
-            } else {
-                None
-            }
+    /// Creates an RGB color from a hue in degrees and a saturation and lightness from 0 to 1.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Color {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        from_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// Returns the hue in degrees and the saturation and lightness from 0 to 1 of this color.
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let rgb = self.rgb_fractions();
+        let max = rgb.0.max(rgb.1).max(rgb.2);
+        let min = rgb.0.min(rgb.1).min(rgb.2);
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
         } else {
-            None
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue(rgb, max, min), saturation, lightness)
+    }
+
+    /// Creates an RGB color from a hue in degrees and a saturation and value from 0 to 1.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation;
+        from_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Returns the hue in degrees and the saturation and value from 0 to 1 of this color.
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let rgb = self.rgb_fractions();
+        let max = rgb.0.max(rgb.1).max(rgb.2);
+        let min = rgb.0.min(rgb.1).min(rgb.2);
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (hue(rgb, max, min), saturation, max)
+    }
+
+    /// Interpolates linearly between this color and `other` in RGB.
+    ///
+    /// `t` is clamped between 0, which returns this color, and 1, which returns `other`, both as RGB colors.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let (r1, g1, b1) = self.rgb();
+        let (r2, g2, b2) = other.rgb();
+        let component = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::Rgb {
+            r: component(r1, r2),
+            g: component(g1, g2),
+            b: component(b1, b2),
+        }
+    }
+
+    /// Returns the color of `foreground` with an `opacity` from 0 to 1 drawn over this color.
+    ///
+    /// Unlike [`Color::lerp`], the colors are mixed in linear light like the light of a translucent surface is.
+    /// For example, white at half opacity over black is a lighter gray than the one halfway between them in RGB.
+    pub fn blend(self, foreground: Color, opacity: f32) -> Color {
+        let opacity = opacity.clamp(0.0, 1.0);
+        let (r1, g1, b1) = self.rgb();
+        let (r2, g2, b2) = foreground.rgb();
+        let component = |background: u8, foreground: u8| {
+            let (background, foreground) = (to_linear(background), to_linear(foreground));
+            from_linear(background + (foreground - background) * opacity)
+        };
+        Color::Rgb {
+            r: component(r1, r2),
+            g: component(g1, g2),
+            b: component(b1, b2),
         }
     }
+
+    /// Makes this color lighter by increasing its HSL lightness by `amount`, up to white.
+    pub fn lighten(self, amount: f32) -> Color {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue, saturation, lightness + amount)
+    }
+
+    /// Makes this color darker by decreasing its HSL lightness by `amount`, down to black.
+    pub fn darken(self, amount: f32) -> Color {
+        self.lighten(-amount)
+    }
+
+    /// Parses a color in any of the following notations:
+    ///
+    /// - Hexadecimal, e.g. `#FF0000`, `#F00` or `FF0000`
+    /// - 8-bit, e.g. `255, 0, 0`, `(255, 0, 0)` or `rgb(255, 0, 0)`
+    /// - Float, e.g. `(1.0, 0.0, 0.0)`
+    /// - Percentage, e.g. `(100%, 0%, 0%)`
+    /// - HSL or HSV with the hue in degrees, e.g. `hsl(0, 100%, 50%)` or `hsv(0deg, 1.0, 1.0)`
+    /// - A [CSS color name](https://www.w3.org/TR/css-color-4/#named-colors), e.g. `red`
+    ///
+    /// Components can be separated by commas or whitespace and notations can be mixed, e.g. `(255, 50%, 0.0)`.
+    /// Numbers with a decimal point are fractions from 0 to 1.
+    /// Letters can be uppercase or lowercase.
+    ///
+    /// The result is always a [`Color::Rgb`], so `darkred` is the CSS color and not [`Color::DarkRed`].
+    /// This is the same as [`str::parse`].
+    pub fn from_rgb(string: &str) -> Result<Color, ParseColorError> {
+        parse::parse(string)
+    }
+
+    /// Parses a hexadecimal color with 3 or 6 digits and an optional `#`, e.g. `dea584` or `#F00`.
+    pub fn from_hex(string: &str) -> Result<Color, ParseColorError> {
+        let hex = string.strip_prefix('#').unwrap_or(string);
+        parse::parse_hex(hex)
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// See [`Color::from_rgb`].
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Color::from_rgb(string)
+    }
 }
 
 #[cfg(test)]
@@ -192,40 +449,196 @@ mod tests {
         Some(Color::Rgb { r, g, b })
     }
 
+    #[test]
+    fn test_to_byte() {
+        assert_eq!(rgb(255, 0, 0).unwrap().to_byte(), Color::Byte(196));
+        assert_eq!(rgb(100, 140, 170).unwrap().to_byte(), Color::Byte(67));
+        assert_eq!(rgb(128, 128, 128).unwrap().to_byte(), Color::Byte(244));
+        assert_eq!(rgb(0, 0, 0).unwrap().to_byte(), Color::Byte(16));
+        assert_eq!(Color::Red.to_byte(), Color::Red);
+        for byte in CUBE_START..=u8::MAX {
+            let (r, g, b) = Color::Byte(byte).rgb();
+            assert_eq!(Color::Rgb { r, g, b }.to_byte().rgb(), (r, g, b));
+        }
+    }
+
+    #[test]
+    fn test_to_four_bit() {
+        assert_eq!(rgb(250, 10, 10).unwrap().to_four_bit(), Color::Red);
+        assert_eq!(rgb(20, 20, 30).unwrap().to_four_bit(), Color::Black);
+        assert_eq!(rgb(140, 130, 120).unwrap().to_four_bit(), Color::DarkGray);
+        assert_eq!(Color::Byte(9).to_four_bit(), Color::Red);
+        assert_eq!(Color::Byte(231).to_four_bit(), Color::White);
+        assert_eq!(Color::DarkCyan.to_four_bit(), Color::DarkCyan);
+    }
+
+    #[test]
+    fn test_rgb() {
+        assert_eq!(Color::Byte(16).rgb(), (0, 0, 0));
+        assert_eq!(Color::Byte(110).rgb(), (135, 175, 215));
+        assert_eq!(Color::Byte(232).rgb(), (8, 8, 8));
+        assert_eq!(Color::Byte(255).rgb(), (238, 238, 238));
+        assert_eq!(Color::Byte(12).to_rgb(), Color::Blue.to_rgb());
+    }
+
+    #[test]
+    fn test_hsl_hsv() {
+        assert_eq!(Color::from_hsl(0.0, 1.0, 0.5), rgb(255, 0, 0).unwrap());
+        assert_eq!(Color::from_hsl(210.0, 0.5, 0.25), rgb(32, 64, 96).unwrap());
+        assert_eq!(Color::from_hsl(-150.0, 0.5, 0.25), rgb(32, 64, 96).unwrap());
+        assert_eq!(Color::from_hsv(120.0, 1.0, 0.5), rgb(0, 128, 0).unwrap());
+        assert_eq!(Color::from_hsv(0.0, 0.0, 1.0), rgb(255, 255, 255).unwrap());
+
+        for &(r, g, b) in &[(32, 64, 96), (255, 128, 0), (10, 200, 100), (77, 77, 77)] {
+            let color = Color::Rgb { r, g, b };
+            let (h, s, l) = color.to_hsl();
+            assert_eq!(Color::from_hsl(h, s, l), color);
+            let (h, s, v) = color.to_hsv();
+            assert_eq!(Color::from_hsv(h, s, v), color);
+        }
+    }
+
+    #[test]
+    fn test_mix() {
+        let black = rgb(0, 0, 0).unwrap();
+        let white = rgb(255, 255, 255).unwrap();
+        assert_eq!(black.lerp(white, 0.5), rgb(128, 128, 128).unwrap());
+        assert_eq!(black.lerp(Color::Red, 2.0), Color::Red.to_rgb());
+        assert_eq!(white.blend(black, 0.25), rgb(225, 225, 225).unwrap());
+        assert_eq!(black.blend(white, 0.5), rgb(188, 188, 188).unwrap());
+        assert_eq!(black.blend(Color::Red, 2.0), Color::Red.to_rgb());
+        for value in 0..=u8::MAX {
+            assert_eq!(from_linear(to_linear(value)), value);
+        }
+        assert_eq!(
+            rgb(32, 64, 96).unwrap().lighten(0.25),
+            rgb(64, 128, 191).unwrap()
+        );
+        assert_eq!(rgb(32, 64, 96).unwrap().darken(1.0), black);
+        assert_eq!(white.lighten(0.5), white);
+    }
+
+    #[test]
+    fn test_invert() {
+        assert_eq!(Color::Black.invert(), Color::White);
+        assert_eq!(Color::DarkRed.invert(), Color::Cyan);
+        assert_eq!(Color::Gray.invert(), Color::DarkGray);
+        assert_eq!(Color::Byte(1).invert(), Color::Byte(14));
+        assert_eq!(Color::Byte(16).invert(), Color::Byte(231));
+        assert_eq!(Color::Byte(110).invert(), Color::Byte(137));
+        assert_eq!(Color::Byte(232).invert(), Color::Byte(255));
+        assert_eq!(
+            Color::Rgb {
+                r: 0,
+                g: 100,
+                b: 255
+            }
+            .invert(),
+            Color::Rgb {
+                r: 255,
+                g: 155,
+                b: 0
+            }
+        );
+
+        // Inverting twice returns the original for every color
+        let mut colors: Vec<Color> = FOUR_BIT_COLORS.to_vec();
+        colors.extend((0..=u8::MAX).map(Color::Byte));
+        colors.extend((0..=u8::MAX).map(|r| Color::Rgb {
+            r,
+            g: r.wrapping_mul(7),
+            b: u8::MAX - r / 3,
+        }));
+        for color in colors {
+            let inverted = color.invert();
+            assert_ne!(inverted, color);
+            assert_eq!(inverted.invert(), color);
+        }
+    }
+
     #[test]
     fn test_parse_rgb_color() {
-        fn parse(string: &str) -> Option<Color> {
-            Color::from_rgb(string)
+        fn parse(string: &str) -> Result<Color, ParseColorError> {
+            string.parse()
         }
+        let ok = |r, g, b| Ok(Color::Rgb { r, g, b });
+
+        assert_eq!(parse("255, 255, 255"), ok(255, 255, 255));
+        assert_eq!(parse("200,255,255"), ok(200, 255, 255));
+        assert_eq!(parse("(255,200,255)"), ok(255, 200, 255));
+        assert_eq!(parse("  rgb(123 255 100)  "), ok(123, 255, 100));
+        assert_eq!(parse("RGB(123,255,100)"), ok(123, 255, 100));
+        assert_eq!(parse("(1.0, 0.0, 0.5)"), ok(255, 0, 128));
+        assert_eq!(parse("(100%, 0%, 50%)"), ok(255, 0, 128));
+        assert_eq!(parse("00FF00"), ok(0, 255, 0));
+        assert_eq!(parse("#00ff00"), ok(0, 255, 0));
+        assert_eq!(parse("#0f8"), ok(0, 255, 136));
+        assert_eq!(parse("hsl(210deg, 50%, 25%)"), ok(32, 64, 96));
+        assert_eq!(parse("hsv(120, 1.0, 0.5)"), ok(0, 128, 0));
+        assert_eq!(parse("RebeccaPurple"), ok(102, 51, 153));
+        assert_eq!(parse("darkred"), ok(139, 0, 0));
 
-        assert_eq!(parse("255, 255, 255"), rgb(255, 255, 255));
-        assert_eq!(parse("200,255,255"), rgb(200, 255, 255));
-        assert_eq!(parse("-200,-255,-255"), rgb(200, 255, 255));
-        assert_eq!(parse("(255,200,255)"), rgb(255, 200, 255));
-        assert_eq!(parse("www255,255,200www"), rgb(255, 255, 200));
-        assert_eq!(parse("    www100www,www0www,www100www"), rgb(100, 0, 100));
-        assert_eq!(parse("www100www,www20www,,,"), rgb(100, 20, 0));
-        assert_eq!(parse("   123"), rgb(123, 0, 0));
-        assert_eq!(parse("99999,99999,99999"), rgb(255, 255, 255));
-        assert_eq!(parse("FF0000,00FF00,0000FF"), rgb(255, 0, 0));
-        assert_eq!(parse("00FF00"), rgb(0, 255, 0));
-        assert_eq!(parse("    00FF00"), rgb(0, 255, 0));
-        assert_eq!(parse("-50,-50,-50-00FF00"), rgb(50, 50, 50));
-        assert_eq!(parse("256"), rgb(255, 0, 0));
-        assert_eq!(parse("99999"), rgb(255, 0, 0));
-        assert_eq!(parse("rgb(123,255,100)"), rgb(123, 255, 100));
-        assert_eq!(parse("123,255,100"), rgb(123, 255, 100));
-        // assert_eq!(parse("255,255,255555555"), rgb(255, 255, 255));
-        // assert_eq!(parse("255,255,255efefef"), rgb(255, 255, 255));
+        assert_eq!(parse("  "), Err(ParseColorError::Empty));
+        assert_eq!(parse("   123"), Err(ParseColorError::ComponentCount(1)));
+        assert_eq!(parse("1.0"), Err(ParseColorError::ComponentCount(1)));
+        assert_eq!(parse("50%"), Err(ParseColorError::ComponentCount(1)));
+        assert_eq!(
+            parse("blurple"),
+            Err(ParseColorError::UnknownName("blurple".into()))
+        );
+        assert_eq!(parse("1,2"), Err(ParseColorError::ComponentCount(2)));
+        assert_eq!(
+            parse("www100www,www20www,,,"),
+            Err(ParseColorError::ComponentCount(2))
+        );
+        assert_eq!(
+            parse("-200,-255,-255"),
+            Err(ParseColorError::InvalidComponent("-200".into()))
+        );
+        assert_eq!(
+            parse("255,255,255555555"),
+            Err(ParseColorError::ComponentOutOfRange("255555555".into()))
+        );
+        assert_eq!(
+            parse("255,255,255efefef"),
+            Err(ParseColorError::InvalidComponent("255efefef".into()))
+        );
+        assert_eq!(
+            parse("(1.5, 0, 0)"),
+            Err(ParseColorError::ComponentOutOfRange("1.5".into()))
+        );
+        assert_eq!(
+            parse("#12345"),
+            Err(ParseColorError::InvalidHex("12345".into()))
+        );
+        assert_eq!(
+            parse("cmyk(1, 2, 3)"),
+            Err(ParseColorError::UnknownFunction("cmyk".into()))
+        );
+        assert_eq!(
+            parse("rgb(1, 2, 3"),
+            Err(ParseColorError::UnbalancedParentheses)
+        );
+        assert_eq!(
+            parse("reddish"),
+            Err(ParseColorError::UnknownName("reddish".into()))
+        );
+        assert_eq!(
+            parse("1,2,300").unwrap_err().to_string(),
+            "color component `300` is out of range"
+        );
     }
 
     #[test]
     fn test_parse_hex() {
         fn parse(string: &str) -> Option<Color> {
-            Color::from_hex(string)
+            Color::from_hex(string).ok()
         }
 
         assert_eq!(parse("dea584"), rgb(222, 165, 132));
         assert_eq!(parse("ff0000"), rgb(255, 0, 0));
+        assert_eq!(parse("#F00"), rgb(255, 0, 0));
+        assert_eq!(parse("ff00"), None);
+        assert_eq!(parse("gg0000"), None);
     }
 }
@@ -0,0 +1,405 @@
+//! Parsing colors from strings.
+
+use super::Color;
+use std::{convert::TryFrom, error, fmt};
+
+/// The error returned when a string can't be parsed into a [`Color`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum ParseColorError {
+    /// The string is empty or only whitespace.
+    Empty,
+    /// A hexadecimal color does not have 3 or 6 hexadecimal digits.
+    InvalidHex(String),
+    /// A component is not a number, percentage or hue.
+    InvalidComponent(String),
+    /// A component is a number that is too small or too large.
+    ComponentOutOfRange(String),
+    /// There are not exactly 3 components.
+    ComponentCount(usize),
+    /// A function other than `rgb`, `hsl` or `hsv` is used.
+    UnknownFunction(String),
+    /// A parenthesis is missing.
+    UnbalancedParentheses,
+    /// The string is not a color notation or a CSS color name.
+    UnknownName(String),
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseColorError::Empty => write!(f, "color is empty"),
+            ParseColorError::InvalidHex(hex) => write!(
+                f,
+                "`{}` is not a hexadecimal color with 3 or 6 hexadecimal digits",
+                hex
+            ),
+            ParseColorError::InvalidComponent(component) => {
+                write!(f, "`{}` is not a valid color component", component)
+            }
+            ParseColorError::ComponentOutOfRange(component) => {
+                write!(f, "color component `{}` is out of range", component)
+            }
+            ParseColorError::ComponentCount(count) => {
+                write!(f, "expected 3 color components but found {}", count)
+            }
+            ParseColorError::UnknownFunction(function) => {
+                write!(f, "unknown color function `{}`", function)
+            }
+            ParseColorError::UnbalancedParentheses => write!(f, "parentheses are unbalanced"),
+            ParseColorError::UnknownName(name) => write!(f, "unknown color name `{}`", name),
+        }
+    }
+}
+
+impl error::Error for ParseColorError {}
+
+pub(super) fn parse(string: &str) -> Result<Color, ParseColorError> {
+    let string = string.trim();
+    if string.is_empty() {
+        return Err(ParseColorError::Empty);
+    }
+    if let Some(hex) = string.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+
+    let lowercase = string.to_ascii_lowercase();
+    if let Some(open) = lowercase.find('(') {
+        let arguments = lowercase[open + 1..]
+            .strip_suffix(')')
+            .filter(|arguments| !arguments.contains(['(', ')']))
+            .ok_or(ParseColorError::UnbalancedParentheses)?;
+        let components = components(arguments)?;
+        match lowercase[..open].trim_end() {
+            "" | "rgb" => rgb(components),
+            "hsl" => {
+                let [hue, saturation, lightness] = hue_components(components)?;
+                Ok(Color::from_hsl(hue, saturation, lightness))
+            }
+            "hsv" => {
+                let [hue, saturation, value] = hue_components(components)?;
+                Ok(Color::from_hsv(hue, saturation, value))
+            }
+            function => Err(ParseColorError::UnknownFunction(function.to_string())),
+        }
+    } else if lowercase.contains(')') {
+        Err(ParseColorError::UnbalancedParentheses)
+    } else if lowercase.contains(|char: char| char == ',' || char.is_whitespace()) {
+        rgb(components(&lowercase)?)
+    } else if lowercase.len() == 6 && lowercase.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        parse_hex(&lowercase)
+    } else if number(lowercase.strip_suffix('%').unwrap_or(&lowercase)).is_ok() {
+        // A lone number is a component rather than a name
+        Err(ParseColorError::ComponentCount(1))
+    } else {
+        named(&lowercase).ok_or_else(|| ParseColorError::UnknownName(string.to_string()))
+    }
+}
+
+/// Parses 3 or 6 hexadecimal digits.
+pub(super) fn parse_hex(hex: &str) -> Result<Color, ParseColorError> {
+    let invalid = || ParseColorError::InvalidHex(hex.to_string());
+
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let digits = match hex.len() {
+        3 => 1,
+        6 => 2,
+        _ => return Err(invalid()),
+    };
+    let component = |index: usize| {
+        let value = u8::from_str_radix(&hex[index * digits..(index + 1) * digits], 16).unwrap();
+        // A single digit is repeated, so that F is FF
+        if digits == 1 {
+            value * 0x11
+        } else {
+            value
+        }
+    };
+    Ok(Color::Rgb {
+        r: component(0),
+        g: component(1),
+        b: component(2),
+    })
+}
+
+/// Parses a color in the X11 format that terminals use in replies to color queries, such as `rgb:ffff/8080/0000`.
+///
+/// Each component has 1 to 4 hexadecimal digits.
+pub(crate) fn parse_x11(string: &str) -> Option<Color> {
+    if let Some(hex) = string.strip_prefix('#') {
+        return parse_hex(hex).ok();
+    }
+
+    let mut components = string.strip_prefix("rgb:")?.split('/').map(|component| {
+        if !(1..=4).contains(&component.len()) {
+            return None;
+        }
+        let value = u32::from_str_radix(component, 16).ok()?;
+        let max = (1 << (4 * component.len())) - 1;
+        Some(((value * 255 + max / 2) / max) as u8)
+    });
+    let color = Color::Rgb {
+        r: components.next()??,
+        g: components.next()??,
+        b: components.next()??,
+    };
+    match components.next() {
+        None => Some(color),
+        Some(_) => None,
+    }
+}
+
+/// Splits exactly 3 components separated by commas or whitespace.
+fn components(string: &str) -> Result<[&str; 3], ParseColorError> {
+    let components: Vec<&str> = string
+        .split(|char: char| char == ',' || char.is_whitespace())
+        .filter(|component| !component.is_empty())
+        .collect();
+    match components[..] {
+        [first, second, third] => Ok([first, second, third]),
+        _ => Err(ParseColorError::ComponentCount(components.len())),
+    }
+}
+
+/// Parses a finite number.
+fn number(component: &str) -> Result<f32, ParseColorError> {
+    component
+        .parse::<f32>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| ParseColorError::InvalidComponent(component.to_string()))
+}
+
+/// Parses a percentage or a number from 0 to 1 into a fraction from 0 to 1.
+fn fraction(component: &str) -> Result<f32, ParseColorError> {
+    let fraction = match component.strip_suffix('%') {
+        Some(percentage) => number(percentage)? / 100.0,
+        None => number(component)?,
+    };
+    if (0.0..=1.0).contains(&fraction) {
+        Ok(fraction)
+    } else {
+        Err(ParseColorError::ComponentOutOfRange(component.to_string()))
+    }
+}
+
+/// Parses RGB components, which are integers from 0 to 255, percentages or numbers with a decimal point from 0 to 1.
+fn rgb(components: [&str; 3]) -> Result<Color, ParseColorError> {
+    let component = |component: &str| {
+        if component.ends_with('%') || component.contains('.') {
+            fraction(component).map(|fraction| (fraction * 255.0).round() as u8)
+        } else {
+            let integer = component
+                .parse::<u32>()
+                .map_err(|_| ParseColorError::InvalidComponent(component.to_string()))?;
+            u8::try_from(integer)
+                .map_err(|_| ParseColorError::ComponentOutOfRange(component.to_string()))
+        }
+    };
+    Ok(Color::Rgb {
+        r: component(components[0])?,
+        g: component(components[1])?,
+        b: component(components[2])?,
+    })
+}
+
+/// Parses a hue in degrees, optionally with a `deg` unit, followed by two fractions.
+fn hue_components(components: [&str; 3]) -> Result<[f32; 3], ParseColorError> {
+    let hue = components[0];
+    Ok([
+        number(hue.strip_suffix("deg").unwrap_or(hue))?,
+        fraction(components[1])?,
+        fraction(components[2])?,
+    ])
+}
+
+/// Looks up a CSS color name.
+fn named(name: &str) -> Option<Color> {
+    let index = NAMED_COLORS
+        .binary_search_by_key(&name, |(name, _)| name)
+        .ok()?;
+    let rgb = NAMED_COLORS[index].1;
+    Some(Color::Rgb {
+        r: (rgb >> 16) as u8,
+        g: (rgb >> 8) as u8,
+        b: rgb as u8,
+    })
+}
+
+/// The CSS named colors, sorted by name.
+///
+/// See <https://www.w3.org/TR/css-color-4/#named-colors>.
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_x11() {
+        let rgb = |r, g, b| Some(Color::Rgb { r, g, b });
+        assert_eq!(parse_x11("rgb:ffff/8080/0000"), rgb(255, 128, 0));
+        assert_eq!(parse_x11("rgb:f/80/000"), rgb(255, 128, 0));
+        assert_eq!(parse_x11("#ff8000"), rgb(255, 128, 0));
+        assert_eq!(parse_x11("rgb:ffff/8080"), None);
+        assert_eq!(parse_x11("rgb:ffff/8080/0000/0000"), None);
+        assert_eq!(parse_x11("rgb:fffff/8080/0000"), None);
+        assert_eq!(parse_x11("?"), None);
+    }
+
+    #[test]
+    fn test_named_colors_sorted() {
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}